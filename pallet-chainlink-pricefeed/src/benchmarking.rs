@@ -0,0 +1,135 @@
+//! Benchmarks of the extrinsics of the Chainlink Price Feed pallet
+//!
+//! The configured Oracle is registered with the Chainlink pallet, so that `request_price` initiates a real request.
+
+use super::*;
+use chainlink::BalanceOf;
+use codec::Encode;
+use frame_benchmarking::{account, benchmarks};
+use frame_support::traits::{Currency, OnInitialize};
+use frame_system::RawOrigin;
+use sp_runtime::traits::{Bounded, One};
+
+const SEED: u32 = 0;
+const JOB_ID: &[u8] = b"benchmark";
+const PRICE_PAIR: &[u8] = b"DOT/USD";
+
+fn funded_account<T: Trait>(name: &'static str, index: u32) -> T::AccountId {
+	let account: T::AccountId = account(name, index, SEED);
+	<T as ChainlinkTrait>::Currency::make_free_balance_be(&account, BalanceOf::<T>::max_value() / 1_000_000u32.into());
+	account
+}
+
+// Register an Oracle supporting `JOB_ID`, and configure this pallet to use it
+fn oracle<T: Trait>() -> Result<T::AccountId, &'static str> {
+	let oracle = funded_account::<T>("oracle", 0);
+	<chainlink::Module<T>>::register_operator(RawOrigin::Signed(oracle.clone()).into())?;
+	<chainlink::Module<T>>::set_specification(RawOrigin::Signed(oracle.clone()).into(), JOB_ID.to_vec(), 1u32.into(), vec![RequestParameters::DATA_VERSION])?;
+	Module::<T>::set_oracle_configuration(RawOrigin::Root.into(), oracle.clone(), JOB_ID.to_vec())?;
+	Ok(oracle)
+}
+
+benchmarks! {
+	_ { }
+
+	set_oracle_configuration {
+		let oracle: T::AccountId = account("oracle", 0, SEED);
+	}: _(RawOrigin::Root, oracle.clone(), JOB_ID.to_vec())
+	verify {
+		assert_eq!(<OracleAccountId<T>>::get(), Some(oracle));
+	}
+
+	request_price {
+		oracle::<T>()?;
+		let caller = funded_account::<T>("caller", 0);
+	}: _(RawOrigin::Signed(caller.clone()), PRICE_PAIR.to_vec())
+	verify {
+		assert_eq!(<chainlink::Module<T>>::pending_requests(&caller), 1);
+	}
+
+	// Worst case: the last price pair allowed
+	add_price_pair {
+		let price_pairs = (1..T::MaxPricePairs::get()).map(|index| index.encode()).collect::<Vec<_>>();
+		<PricePairs>::put(price_pairs);
+	}: _(RawOrigin::Root, PRICE_PAIR.to_vec())
+	verify {
+		assert!(Module::<T>::price_pairs().contains(&PRICE_PAIR.to_vec()));
+	}
+
+	// Worst case: the answer is checked against the previous price, and the oldest price of a full history is pruned
+	callback {
+		<Prices<T>>::insert(PRICE_PAIR.to_vec(), PriceData { value: 41, decimals: T::PriceDecimals::get(), ..Default::default() });
+		let history = (0..T::PriceHistoryDepth::get()).map(|index| (T::BlockNumber::from(index), 41i128)).collect::<Vec<_>>();
+		<PriceHistory<T>>::insert(PRICE_PAIR.to_vec(), history);
+	}: _(RawOrigin::Root, PRICE_PAIR.to_vec(), (42i128, T::PriceDecimals::get()).encode())
+	verify {
+		assert_eq!(Module::<T>::get_price(PRICE_PAIR).map(|price| price.value), Some(42));
+		assert_eq!(Module::<T>::price_history(PRICE_PAIR).len(), T::PriceHistoryDepth::get() as usize);
+	}
+
+	top_up {
+		let source = funded_account::<T>("source", 0);
+		let amount = <T as ChainlinkTrait>::Currency::minimum_balance() * 10u32.into();
+	}: _(RawOrigin::Signed(source), amount)
+	verify {
+		assert_eq!(<T as ChainlinkTrait>::Currency::free_balance(&Module::<T>::account_id()), amount);
+	}
+
+	remove_price_pair {
+		<PricePairs>::put(vec![PRICE_PAIR.to_vec()]);
+		<Prices<T>>::insert(PRICE_PAIR.to_vec(), PriceData { value: 42, ..Default::default() });
+	}: _(RawOrigin::Root, PRICE_PAIR.to_vec())
+	verify {
+		assert!(Module::<T>::price_pairs().is_empty());
+		assert!(!<Prices<T>>::contains_key(PRICE_PAIR.to_vec()));
+	}
+
+	confirm_quarantined_price {
+		<QuarantinedPrices<T>>::insert(PRICE_PAIR.to_vec(), (T::BlockNumber::zero(), 42, T::PriceDecimals::get()));
+	}: _(RawOrigin::Root, PRICE_PAIR.to_vec())
+	verify {
+		assert_eq!(Module::<T>::get_price(PRICE_PAIR).map(|price| price.value), Some(42));
+	}
+
+	discard_quarantined_price {
+		<QuarantinedPrices<T>>::insert(PRICE_PAIR.to_vec(), (T::BlockNumber::zero(), 42, T::PriceDecimals::get()));
+	}: _(RawOrigin::Root, PRICE_PAIR.to_vec())
+	verify {
+		assert!(Module::<T>::quarantined_price(PRICE_PAIR).is_none());
+	}
+
+	// A refresh requested from `on_initialize`
+	refresh_price {
+		oracle::<T>()?;
+		<PricePairs>::put(vec![PRICE_PAIR.to_vec()]);
+		let account = Module::<T>::account_id();
+		<T as ChainlinkTrait>::Currency::make_free_balance_be(&account, BalanceOf::<T>::max_value() / 1_000_000u32.into());
+		let now = frame_system::Module::<T>::block_number().saturating_add(T::RefreshInterval::get()).max(One::one());
+	}: { Module::<T>::on_initialize(now); }
+	verify {
+		assert_eq!(Module::<T>::last_refresh(PRICE_PAIR), now);
+		assert_eq!(<chainlink::Module<T>>::pending_requests(&account), 1);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mock::{new_test_ext, Test};
+	use frame_support::assert_ok;
+
+	#[test]
+	fn benchmarks_run() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_oracle_configuration::<Test>());
+			assert_ok!(test_benchmark_request_price::<Test>());
+			assert_ok!(test_benchmark_callback::<Test>());
+			assert_ok!(test_benchmark_add_price_pair::<Test>());
+			assert_ok!(test_benchmark_top_up::<Test>());
+			assert_ok!(test_benchmark_remove_price_pair::<Test>());
+			assert_ok!(test_benchmark_confirm_quarantined_price::<Test>());
+			assert_ok!(test_benchmark_discard_quarantined_price::<Test>());
+			assert_ok!(test_benchmark_refresh_price::<Test>());
+		});
+	}
+}
@@ -0,0 +1,53 @@
+//! Default weights of the extrinsics of the Chainlink Price Feed pallet, used with `type WeightInfo = ()`
+//!
+//! `request_price` and `refresh_price` include the request initiated through the Chainlink pallet. Execution times should be refreshed
+//! from the `pricefeed` benchmarks whenever the extrinsics change.
+
+use frame_support::weights::{Weight, constants::RocksDbWeight as DbWeight};
+
+impl crate::WeightInfo for () {
+	fn set_oracle_configuration() -> Weight {
+		(11_000_000 as Weight)
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	fn request_price() -> Weight {
+		(134_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(12 as Weight))
+			.saturating_add(DbWeight::get().writes(12 as Weight))
+	}
+	fn add_price_pair() -> Weight {
+		(14_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn callback() -> Weight {
+		(27_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	fn top_up() -> Weight {
+		(58_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	fn remove_price_pair() -> Weight {
+		(28_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(6 as Weight))
+	}
+	fn confirm_quarantined_price() -> Weight {
+		(41_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(4 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
+	fn discard_quarantined_price() -> Weight {
+		(22_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn refresh_price() -> Weight {
+		(137_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(13 as Weight))
+			.saturating_add(DbWeight::get().writes(11 as Weight))
+	}
+}
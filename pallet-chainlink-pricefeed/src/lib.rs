@@ -1,8 +1,12 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use chainlink::{CallbackWithParameter, Event, Trait as ChainlinkTrait};
+use chainlink::{BalanceOf, CallbackWithParameter, Trait as ChainlinkTrait};
+use chainlink::encoding::{decode_result, RequestParameters};
 use codec::{Decode, Encode};
-use frame_support::{decl_module, decl_storage, dispatch::DispatchResult};
+use frame_support::{decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResult, ensure, weights::Weight};
+use frame_support::storage::migration::take_storage_value;
+use frame_support::traits::{Currency, ExistenceRequirement, Get};
+use sp_runtime::{FixedPointNumber, FixedU128, ModuleId, Perbill, RuntimeDebug, traits::{AccountIdConversion, AtLeast32BitUnsigned, Saturating, UniqueSaturatedInto, Zero}};
 use sp_std::prelude::*;
 use frame_system::{ensure_root, ensure_signed};
 use log::info;
@@ -13,38 +17,296 @@ mod mock;
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "runtime-benchmarks")]
+pub mod benchmarking;
+mod default_weights;
+
+const PRICEFEED_ACCOUNT_ID: ModuleId = ModuleId(*b"py/prfed");
+
+/// The number of prices received for a price pair, counted on chain
+/// This is not the round of the Oracle aggregator, which the Oracle job doesn't report.
+pub type AnswerCount = u64;
+
+/// A price received from the Oracle
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct PriceData<BlockNumber, Moment> {
+	/// The price value, with `decimals` decimals
+	pub value: i128,
+	/// The block at which the price was received
+	pub block_number: BlockNumber,
+	/// The timestamp of the block at which the price was received
+	pub timestamp: Moment,
+	/// The number of prices received for the price pair, this one included
+	pub answer_count: AnswerCount,
+	/// The number of decimals of the price value, as reported by the Oracle
+	pub decimals: u8,
+}
+
+impl<BlockNumber, Moment> PriceData<BlockNumber, Moment> {
+	/// The price as a fixed-point number, see `FixedPrice::from_raw`
+	pub fn fixed(&self) -> Option<FixedPrice> {
+		FixedPrice::from_raw(self.value, self.decimals)
+	}
+}
+
+/// A price as a fixed-point number, along with the number of decimals of the raw price it comes from
+#[derive(Encode, Decode, Clone, Copy, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct FixedPrice {
+	/// The price value
+	pub value: FixedU128,
+	/// The number of decimals of the raw price, and the precision of `value`
+	pub decimals: u8,
+}
+
+impl FixedPrice {
+	/// The price `value / 10^decimals`, unless `value` is negative or doesn't fit a `FixedU128`
+	pub fn from_raw(value: i128, decimals: u8) -> Option<Self> {
+		if value < 0 {
+			return None;
+		}
+		let divisor = 10u128.checked_pow(decimals as u32)?;
+		FixedU128::checked_from_rational(value as u128, divisor).map(|value| FixedPrice { value, decimals })
+	}
+}
+
+/// Why a checked price can't be used
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum PriceError {
+	/// No price was received for the price pair
+	Missing,
+	/// The latest price is older than the accepted age
+	Stale,
+	/// An answer out of the sanity bounds is waiting for governance to confirm or discard it
+	Quarantined,
+	/// The latest price can't be a `FixedPrice`, e.g. because it is negative
+	Invalid,
+}
+
 pub trait PriceFeeds {
-	fn latest_price() -> i128;
+	type BlockNumber;
+	type Moment;
+
+	/// The latest price value received for `pair`, e.g. `b"ETH/USD"`, or `0` if none was received
+	fn latest_price(pair: &[u8]) -> i128;
+
+	/// The latest price received for `pair`, unless none was received, it is more than `max_age` blocks old, or an answer is quarantined
+	fn latest_price_checked(pair: &[u8], max_age: Self::BlockNumber) -> Result<PriceData<Self::BlockNumber, Self::Moment>, PriceError>;
+
+	/// The latest price of `pair` as a fixed-point number, checked as by `latest_price_checked`
+	fn fixed_price_checked(pair: &[u8], max_age: Self::BlockNumber) -> Result<FixedPrice, PriceError> {
+		Self::latest_price_checked(pair, max_age)?.fixed().ok_or(PriceError::Invalid)
+	}
+
+	/// The time-weighted average price of `pair` over the last `window` blocks, see `time_weighted_average`
+	/// `None` while an answer for `pair` is quarantined.
+	fn twap(pair: &[u8], window: Self::BlockNumber) -> Option<FixedPrice>;
+
+	/// The median of the prices of `pair` received in the last `window` blocks, see `median`
+	/// `None` while an answer for `pair` is quarantined.
+	fn median_price(pair: &[u8], window: Self::BlockNumber) -> Option<FixedPrice>;
 }
 
-pub trait Trait: ChainlinkTrait {
+/// The average of the prices of `history`, oldest first, over the `window` blocks before `now`
+/// Each price is weighted by the number of blocks it was the latest one, a price received before the window counting from its start.
+/// Prices received in block `now` don't have any weight, unless there's no other price in the window.
+pub fn time_weighted_average<BlockNumber>(history: &[(BlockNumber, i128)], now: BlockNumber, window: BlockNumber) -> Option<i128>
+	where BlockNumber: AtLeast32BitUnsigned + Copy
+{
+	let start = now.saturating_sub(window);
+	let mut weighted_sum: i128 = 0;
+	let mut total_blocks: i128 = 0;
+	for (index, (block_number, value)) in history.iter().enumerate() {
+		let from = (*block_number).max(start);
+		let to = history.get(index + 1).map_or(now, |(next_block_number, _)| *next_block_number);
+		if to > from {
+			let blocks = UniqueSaturatedInto::<u64>::unique_saturated_into(to - from) as i128;
+			weighted_sum = weighted_sum.saturating_add(value.saturating_mul(blocks));
+			total_blocks = total_blocks.saturating_add(blocks);
+		}
+	}
+	if total_blocks == 0 {
+		return history.last().map(|(_, value)| *value);
+	}
+	Some(weighted_sum / total_blocks)
+}
+
+/// The median of the prices of `history` received since the block `now - window`
+/// The mean of the two middle prices is used for an even number of prices, as for aggregated Chainlink answers.
+pub fn median<BlockNumber>(history: &[(BlockNumber, i128)], now: BlockNumber, window: BlockNumber) -> Option<i128>
+	where BlockNumber: AtLeast32BitUnsigned + Copy
+{
+	let start = now.saturating_sub(window);
+	let mut values = history.iter().filter(|(block_number, _)| *block_number >= start).map(|(_, value)| *value).collect::<Vec<_>>();
+	chainlink::median(&mut values)
+}
+
+/// `value`, with `from` decimals, expressed with `to` decimals, rounded towards zero
+/// `None` if it overflows.
+pub fn rescale(value: i128, from: u8, to: u8) -> Option<i128> {
+	if from >= to {
+		Some(10i128.checked_pow((from - to) as u32).map_or(0, |divisor| value / divisor))
+	} else {
+		value.checked_mul(10i128.checked_pow((to - from) as u32)?)
+	}
+}
+
+/// Storage layouts of the pallet, used to migrate storage on runtime upgrades
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum Releases {
+	/// A single price stored in `Price`, for the price pair stored in `PricePair`
+	V1,
+	/// Prices stored by price pair as `PriceData`
+	V2,
+}
+
+impl Default for Releases {
+	fn default() -> Self {
+		Releases::V1
+	}
+}
+
+pub trait Trait: ChainlinkTrait + pallet_timestamp::Trait {
 	/// Because this pallet emits events, it depends on the runtime's definition of an event.
-	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+	type Event: From<Event> + Into<<Self as frame_system::Trait>::Event>;
 
 	/// We need to provide our callback to Chainlink pallet
 	type Callback: From<Call<Self>> + Into<<Self as ChainlinkTrait>::Callback>;
 
+	/// Age, in blocks, from which the price is automatically refreshed, `0` disabling the refresh
+	type RefreshInterval: Get<Self::BlockNumber>;
+
+	/// Fee of each refresh request, paid from the pallet account
+	type RefreshFee: Get<BalanceOf<Self>>;
+
+	/// Fee of each price request initiated by an account, paid by this account
+	type RequestFee: Get<BalanceOf<Self>>;
+
+	/// The JobId on the Oracle which trigger calls to the Price Feed Adapter, unless one is set in storage
+	type DefaultOracleJobId: Get<Vec<u8>>;
+
+	/// The Oracle Operator receiving the price requests, unless one is set in storage
+	type DefaultOracleAccountId: Get<Option<Self::AccountId>>;
+
+	/// Maximum number of price pairs added by governance, all of them being refreshed
+	type MaxPricePairs: Get<u32>;
+
+	/// Number of decimals of the sanity bounds and of the price history, answers being rescaled to it
+	type PriceDecimals: Get<u8>;
+
+	/// Number of prices kept in the history of each price pair, to average them
+	type PriceHistoryDepth: Get<u32>;
+
+	/// Lowest sane price value, with `PriceDecimals` decimals, lower answers being quarantined
+	type MinPrice: Get<i128>;
+
+	/// Highest sane price value, with `PriceDecimals` decimals, higher answers being quarantined
+	type MaxPrice: Get<i128>;
+
+	/// Largest sane deviation of an answer from the previous price of its pair, larger ones being quarantined
+	type MaxPriceDeviation: Get<Perbill>;
+
+	/// Weights of the extrinsics, `()` provides default ones
+	type WeightInfo: WeightInfo;
+}
+
+/// Weights of the extrinsics of this pallet
+pub trait WeightInfo {
+	fn set_oracle_configuration() -> Weight;
+	fn request_price() -> Weight;
+	fn add_price_pair() -> Weight;
+	fn callback() -> Weight;
+	fn top_up() -> Weight;
+	fn remove_price_pair() -> Weight;
+	fn confirm_quarantined_price() -> Weight;
+	fn discard_quarantined_price() -> Weight;
+	fn refresh_price() -> Weight;
 }
 
 decl_storage! {
     trait Store for Module<T: Trait> as PriceFeedStorage {
-    	/// Store the latest price pair requested
-        pub PricePair: Vec<u8>;
-    	/// Store the price value received from Chainlink
-        pub Price  get(fn get_price): i128;
-		/// The JobId on the Oracle which trigger calls to the Price Feed Adapter
+    	/// Store the price pairs added by governance, refreshed side by side
+        pub PricePairs get(fn price_pairs): Vec<Vec<u8>>;
+    	/// Store the prices received from Chainlink, by price pair
+        pub Prices get(fn get_price): map hasher(blake2_128_concat) Vec<u8> => Option<PriceData<T::BlockNumber, T::Moment>>;
+		/// The JobId on the Oracle which trigger calls to the Price Feed Adapter, `DefaultOracleJobId` being used if empty
 		pub OracleJobId: Vec<u8>;
-		/// The AccountId set in the Oracle Job Initiator
-		pub OracleAccountId: T::AccountId;
+		/// The AccountId set in the Oracle Job Initiator, `DefaultOracleAccountId` being used if missing
+		pub OracleAccountId: Option<T::AccountId>;
+		/// The latest `PriceHistoryDepth` prices received for each price pair, with `PriceDecimals` decimals and the block they were received at, oldest first
+		pub PriceHistory get(fn price_history): map hasher(blake2_128_concat) Vec<u8> => Vec<(T::BlockNumber, i128)>;
+		/// The block at which the latest refresh of a pair was requested
+		pub LastRefresh get(fn last_refresh): map hasher(blake2_128_concat) Vec<u8> => T::BlockNumber;
+		/// The latest answer out of the sanity bounds received for a price pair, with the block it was received at and its decimals
+		/// The price pair is paused until governance confirms or discards it.
+		pub QuarantinedPrices get(fn quarantined_price): map hasher(blake2_128_concat) Vec<u8> => Option<(T::BlockNumber, i128, u8)>;
+		/// The storage layout, missing before prices were stored by price pair
+		pub StorageVersion get(fn storage_version): Releases;
     }
+	add_extra_genesis {
+		/// The Oracle configuration, overriding `DefaultOracleAccountId` and `DefaultOracleJobId`
+		config(oracle_account_id): Option<T::AccountId>;
+		config(oracle_job_id): Vec<u8>;
+		/// A price pair refreshed from the first block, if not empty
+		config(price_pair): Vec<u8>;
+		/// The price of `price_pair` until the Oracle answers, with `PriceDecimals` decimals, received at block 0
+		config(price): Option<i128>;
+		build(|config: &GenesisConfig<T>| {
+			if let Some(account_id) = &config.oracle_account_id {
+				<OracleAccountId<T>>::put(account_id);
+			}
+			<OracleJobId>::put(&config.oracle_job_id);
+			if !config.price_pair.is_empty() {
+				<PricePairs>::put(vec![config.price_pair.clone()]);
+				if let Some(price) = config.price {
+					Module::<T>::store_price(&config.price_pair, price, T::PriceDecimals::get(), Zero::zero());
+				}
+			}
+			StorageVersion::put(Releases::V2);
+		});
+	}
+}
+
+decl_event!(
+	pub enum Event {
+		/// An answer out of the sanity bounds was quarantined, pausing its price pair. [price_pair, value, decimals]
+		PriceQuarantined(Vec<u8>, i128, u8),
+		/// Governance confirmed the quarantined answer of a price pair, which is now its price. [price_pair, value, decimals]
+		QuarantinedPriceConfirmed(Vec<u8>, i128, u8),
+		/// Governance discarded the quarantined answer of a price pair. [price_pair, value, decimals]
+		QuarantinedPriceDiscarded(Vec<u8>, i128, u8),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// There are already `MaxPricePairs` price pairs
+		TooManyPricePairs,
+		/// No answer of the price pair is quarantined
+		NotQuarantined,
+		/// Neither `OracleAccountId` nor `DefaultOracleAccountId` is set
+		NoOracle,
+	}
 }
 
 decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
 		// Events must be initialized if they are used by the pallet.
 		fn deposit_event() = default;
 
-		#[weight = 0]
+		const RefreshInterval: T::BlockNumber = T::RefreshInterval::get();
+		const RefreshFee: BalanceOf<T> = T::RefreshFee::get();
+		const RequestFee: BalanceOf<T> = T::RequestFee::get();
+		const DefaultOracleJobId: Vec<u8> = T::DefaultOracleJobId::get();
+		const MaxPricePairs: u32 = T::MaxPricePairs::get();
+		const PriceDecimals: u8 = T::PriceDecimals::get();
+		const PriceHistoryDepth: u32 = T::PriceHistoryDepth::get();
+		const MinPrice: i128 = T::MinPrice::get();
+		const MaxPrice: i128 = T::MaxPrice::get();
+		const MaxPriceDeviation: Perbill = T::MaxPriceDeviation::get();
+
+		#[weight = <T as Trait>::WeightInfo::set_oracle_configuration()]
         pub fn set_oracle_configuration(origin, account_id: T::AccountId, job_id: Vec<u8>) -> DispatchResult {
 			ensure_root(origin)?;
 			<OracleJobId>::put(job_id);
@@ -52,51 +314,272 @@ decl_module! {
             Ok(())
         }
 
+		// Request the price of `price_pair` from `operator` rather than from the configured Oracle
+		// `RequestFee` is paid by the caller. The price pair is not refreshed unless it has been added with `add_price_pair`.
+		#[weight = <T as Trait>::WeightInfo::request_price()]
+        pub fn send_request(origin, operator: T::AccountId, price_pair: Vec<u8>) -> DispatchResult {
+			Self::request_price_from(origin, operator, price_pair)
+        }
 
-		#[weight = 0]
+
+		// Request the price of `price_pair` once from the configured Oracle
+		// `RequestFee` is paid by the caller. The price pair is not refreshed unless it has been added with `add_price_pair`.
+		#[weight = <T as Trait>::WeightInfo::request_price()]
         pub fn request_price(origin, price_pair: Vec<u8>) -> DispatchResult {
+			let operator = Self::oracle_account_id().ok_or(Error::<T>::NoOracle)?;
+			Self::request_price_from(origin, operator, price_pair)
+        }
 
-			info!("Request Price for {:?} using {:?}", price_pair.clone(), <OracleJobId>::get());
-			ensure_signed(origin.clone())?;
-            let parameters = ("pricePair", price_pair.clone());
-            let call: <T as Trait>::Callback = Call::callback(vec![]).into();
+		// Start refreshing a price pair, from the pallet account
+		// Fails with `TooManyPricePairs` for a new pair once there are `MaxPricePairs` pairs.
+		#[weight = <T as Trait>::WeightInfo::add_price_pair()]
+		pub fn add_price_pair(origin, price_pair: Vec<u8>) -> DispatchResult {
+			ensure_root(origin)?;
+			let mut price_pairs = Self::price_pairs();
+			if !price_pairs.contains(&price_pair) {
+				ensure!((price_pairs.len() as u32) < T::MaxPricePairs::get(), Error::<T>::TooManyPricePairs);
+				price_pairs.push(price_pair);
+				<PricePairs>::put(price_pairs);
+			}
+			Ok(())
+		}
 
-			info!("Calling initiate_request");
-			<PricePair>::put(price_pair);
-			<chainlink::Module<T>>::initiate_request(origin, <OracleAccountId<T>>::get(), <OracleJobId>::get(), 0, parameters.encode(), 100, call.into())?;
+		// Transfer `amount` from the caller to the pallet account, which pays the refresh requests
+		#[weight = <T as Trait>::WeightInfo::top_up()]
+		pub fn top_up(origin, amount: BalanceOf<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			<T as ChainlinkTrait>::Currency::transfer(&who, &Self::account_id(), amount, ExistenceRequirement::KeepAlive)
+		}
 
-            Ok(())
-        }
+		// Stop refreshing a price pair, and forget its price
+		#[weight = <T as Trait>::WeightInfo::remove_price_pair()]
+		pub fn remove_price_pair(origin, price_pair: Vec<u8>) -> DispatchResult {
+			ensure_root(origin)?;
+			<PricePairs>::mutate(|price_pairs| price_pairs.retain(|pair| *pair != price_pair));
+			<Prices<T>>::remove(&price_pair);
+			<PriceHistory<T>>::remove(&price_pair);
+			<QuarantinedPrices<T>>::remove(&price_pair);
+			<LastRefresh<T>>::remove(&price_pair);
+			Ok(())
+		}
 
 		// The callback is called by the pallet-chainlink upon result returned by the Chainlink job
-		// The result parameter hold the price value
-		#[weight = 0]
-        pub fn callback(origin, result: Vec<u8>) -> DispatchResult {
-        	info!("Calling callback");
+		// The price_pair parameter is the requested pair, and the result parameter hold its price value and decimals
+		#[weight = <T as Trait>::WeightInfo::callback()]
+        pub fn callback(origin, price_pair: Vec<u8>, result: Vec<u8>) -> DispatchResult {
+        	info!("Calling callback for {:?}", price_pair);
             ensure_root(origin)?;
 
-            // The result is expected to be a SCALE encoded `i128`
-            let r : i128 = i128::decode(&mut &result[..]).map_err(|err| err.what())?;
-            <Price>::put(r);
+            // The result is expected to be a SCALE encoded `(i128, u8)`: the price value and its number of decimals
+            let (r, decimals) : (i128, u8) = decode_result(&result).map_err(|err| err.what())?;
+			let now = <frame_system::Module<T>>::block_number();
+			if Self::is_sane(&price_pair, r, decimals) {
+				Self::store_price(&price_pair, r, decimals, now);
+			} else {
+				info!("Quarantining {:?} with {:?} decimals for {:?}", r, decimals, price_pair);
+				<QuarantinedPrices<T>>::insert(&price_pair, (now, r, decimals));
+				Self::deposit_event(Event::PriceQuarantined(price_pair, r, decimals));
+			}
 
             Ok(())
         }
 
+		// Accept the quarantined answer of a price pair as its latest price, resuming the price pair
+		#[weight = <T as Trait>::WeightInfo::confirm_quarantined_price()]
+		pub fn confirm_quarantined_price(origin, price_pair: Vec<u8>) -> DispatchResult {
+			ensure_root(origin)?;
+			let (_, value, decimals) = <QuarantinedPrices<T>>::take(&price_pair).ok_or(Error::<T>::NotQuarantined)?;
+			Self::store_price(&price_pair, value, decimals, <frame_system::Module<T>>::block_number());
+			Self::deposit_event(Event::QuarantinedPriceConfirmed(price_pair, value, decimals));
+			Ok(())
+		}
+
+		// Drop the quarantined answer of a price pair, resuming the price pair with its previous price
+		#[weight = <T as Trait>::WeightInfo::discard_quarantined_price()]
+		pub fn discard_quarantined_price(origin, price_pair: Vec<u8>) -> DispatchResult {
+			ensure_root(origin)?;
+			let (_, value, decimals) = <QuarantinedPrices<T>>::take(&price_pair).ok_or(Error::<T>::NotQuarantined)?;
+			Self::deposit_event(Event::QuarantinedPriceDiscarded(price_pair, value, decimals));
+			Ok(())
+		}
+
+		// Request each price pair again once its price is `RefreshInterval` blocks old, at most once per `RefreshInterval`
+		// A failed refresh, e.g. because the pallet account can't pay its fee, is retried after `RefreshInterval` blocks.
+		fn on_initialize(now: T::BlockNumber) -> Weight {
+			let interval = T::RefreshInterval::get();
+			if interval.is_zero() {
+				return 0;
+			}
+			let mut weight = T::DbWeight::get().reads(1);
+			for price_pair in Self::price_pairs() {
+				weight = weight.saturating_add(T::DbWeight::get().reads(2));
+				if !Self::refresh_due(&price_pair, now, interval) {
+					continue;
+				}
+				<LastRefresh<T>>::insert(&price_pair, now);
+				let origin = frame_system::RawOrigin::Signed(Self::account_id()).into();
+				let request = match Self::oracle_account_id() {
+					Some(operator) => Self::initiate_price_request(origin, operator, price_pair.clone(), T::RefreshFee::get()),
+					None => Err(Error::<T>::NoOracle.into()),
+				};
+				if let Err(error) = request {
+					info!("Price refresh for {:?} failed: {:?}", price_pair, error);
+				}
+				weight = weight.saturating_add(<T as Trait>::WeightInfo::refresh_price());
+			}
+			weight
+		}
+
+		fn on_runtime_upgrade() -> Weight {
+			let mut weight = T::DbWeight::get().reads(1);
+			if StorageVersion::get() == Releases::V1 {
+				weight = weight.saturating_add(Self::migrate_to_v2());
+			}
+			weight
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The account ID paying the refresh requests
+	pub fn account_id() -> T::AccountId {
+		PRICEFEED_ACCOUNT_ID.into_account()
+	}
+
+	/// The JobId used by the price requests
+	pub fn oracle_job_id() -> Vec<u8> {
+		let job_id = <OracleJobId>::get();
+		if job_id.is_empty() { T::DefaultOracleJobId::get() } else { job_id }
+	}
+
+	/// The Oracle Operator receiving the price requests, if any is configured
+	pub fn oracle_account_id() -> Option<T::AccountId> {
+		<OracleAccountId<T>>::get().or_else(T::DefaultOracleAccountId::get)
+	}
+
+	fn request_price_from(origin: T::Origin, operator: T::AccountId, price_pair: Vec<u8>) -> DispatchResult {
+		info!("Request Price for {:?} using {:?}", price_pair, Self::oracle_job_id());
+		ensure_signed(origin.clone())?;
+		Self::initiate_price_request(origin, operator, price_pair, T::RequestFee::get())
+	}
+
+	fn refresh_due(price_pair: &[u8], now: T::BlockNumber, interval: T::BlockNumber) -> bool {
+		let last_update = Self::get_price(price_pair).map(|price| price.block_number).unwrap_or_default();
+		now.saturating_sub(last_update) >= interval
+			&& now.saturating_sub(Self::last_refresh(price_pair)) >= interval
+	}
+
+	// Whether `value`, with `decimals` decimals, is within the sanity bounds, including the deviation from the previous price of `price_pair`
+	// Both are compared with `PriceDecimals` decimals, an answer overflowing them being insane.
+	fn is_sane(price_pair: &[u8], value: i128, decimals: u8) -> bool {
+		let value = match rescale(value, decimals, T::PriceDecimals::get()) {
+			Some(value) => value,
+			None => return false,
+		};
+		if value < T::MinPrice::get() || value > T::MaxPrice::get() {
+			return false;
+		}
+		Self::get_price(price_pair)
+			.and_then(|previous| rescale(previous.value, previous.decimals, T::PriceDecimals::get()))
+			.map_or(true, |previous| {
+				let abs = |value: i128| value.checked_abs().unwrap_or(i128::max_value()) as u128;
+				abs(value.saturating_sub(previous)) <= T::MaxPriceDeviation::get() * abs(previous)
+			})
+	}
+
+	// Record `value`, with `decimals` decimals, as the latest price of `price_pair`, received at block `now`
+	// It is left out of the history if it overflows `PriceDecimals` decimals.
+	fn store_price(price_pair: &[u8], value: i128, decimals: u8, now: T::BlockNumber) {
+		let answer_count = Self::get_price(price_pair).map_or(0, |price| price.answer_count).saturating_add(1);
+		<Prices<T>>::insert(price_pair, PriceData {
+			value,
+			block_number: now,
+			timestamp: <pallet_timestamp::Module<T>>::get(),
+			answer_count,
+			decimals,
+		});
+		if let Some(value) = rescale(value, decimals, T::PriceDecimals::get()) {
+			<PriceHistory<T>>::mutate(price_pair, |history| {
+				history.push((now, value));
+				let excess = history.len().saturating_sub(T::PriceHistoryDepth::get() as usize);
+				history.drain(..excess);
+			});
+		}
+	}
+
+	fn initiate_price_request(origin: T::Origin, operator: T::AccountId, price_pair: Vec<u8>, fee: BalanceOf<T>) -> DispatchResult {
+		let parameters = RequestParameters::new().with("pricePair", price_pair.clone()).result::<(i128, u8)>();
+		let call: <T as Trait>::Callback = Call::callback(price_pair, vec![]).into();
+
+		info!("Calling initiate_request");
+		<chainlink::Module<T>>::initiate_request(origin, operator, Self::oracle_job_id(), RequestParameters::DATA_VERSION, parameters.into_data(), fee, None, call.into())
+	}
+
+	// Move the single price stored before prices were kept side by side, as the `PriceData` of its pair
+	// Its timestamp and the block at which it was received are unknown, and it is not counted as an answer.
+	// The Oracle didn't report decimals then: they are assumed to be `PriceDecimals`.
+	fn migrate_to_v2() -> Weight {
+		let module = b"PriceFeedStorage";
+		let price_pair = take_storage_value::<Vec<u8>>(module, b"PricePair", &[]);
+		let price = take_storage_value::<i128>(module, b"Price", &[]);
+		let mut weight = T::DbWeight::get().reads_writes(2, 2);
+		if let Some(price_pair) = price_pair.filter(|price_pair| !price_pair.is_empty()) {
+			if let Some(value) = price {
+				<Prices<T>>::insert(&price_pair, PriceData {
+					value,
+					block_number: Zero::zero(),
+					timestamp: Default::default(),
+					answer_count: 0,
+					decimals: T::PriceDecimals::get(),
+				});
+			}
+			<PricePairs>::put(vec![price_pair]);
+			weight = weight.saturating_add(T::DbWeight::get().writes(2));
+		}
+		StorageVersion::put(Releases::V2);
+		weight.saturating_add(T::DbWeight::get().writes(1))
 	}
 }
 
 impl <T: Trait> CallbackWithParameter for Call<T> {
 	fn with_result(&self, result: Vec<u8>) -> Option<Self> {
 		match *self {
-			Call::callback(_) => Some(Call::callback(result)),
+			Call::callback(ref price_pair, _) => Some(Call::callback(price_pair.clone(), result)),
 			_ => None
 		}
 	}
 }
 
 impl<T: Trait> PriceFeeds for Module<T> {
-	fn latest_price() -> i128{
-		return Self::get_price();
+	type BlockNumber = T::BlockNumber;
+	type Moment = T::Moment;
+
+	fn latest_price(pair: &[u8]) -> i128{
+		return Self::get_price(pair).map_or(0, |price| price.value);
+	}
+
+	fn latest_price_checked(pair: &[u8], max_age: T::BlockNumber) -> Result<PriceData<T::BlockNumber, T::Moment>, PriceError> {
+		ensure!(!<QuarantinedPrices<T>>::contains_key(pair), PriceError::Quarantined);
+		let price = Self::get_price(pair).ok_or(PriceError::Missing)?;
+		let age = <frame_system::Module<T>>::block_number().saturating_sub(price.block_number);
+		ensure!(age <= max_age, PriceError::Stale);
+		Ok(price)
+	}
+
+	fn twap(pair: &[u8], window: T::BlockNumber) -> Option<FixedPrice> {
+		if <QuarantinedPrices<T>>::contains_key(pair) {
+			return None;
+		}
+		time_weighted_average(&Self::price_history(pair), <frame_system::Module<T>>::block_number(), window)
+			.and_then(|value| FixedPrice::from_raw(value, T::PriceDecimals::get()))
+	}
+
+	fn median_price(pair: &[u8], window: T::BlockNumber) -> Option<FixedPrice> {
+		if <QuarantinedPrices<T>>::contains_key(pair) {
+			return None;
+		}
+		median(&Self::price_history(pair), <frame_system::Module<T>>::block_number(), window)
+			.and_then(|value| FixedPrice::from_raw(value, T::PriceDecimals::get()))
 	}
 }
 
@@ -0,0 +1,217 @@
+use crate::{Module, Trait};
+use chainlink::encoding::RequestParameters;
+use codec::Encode;
+use frame_support::{impl_outer_dispatch, impl_outer_event, impl_outer_origin, parameter_types, traits::UnfilteredDispatchable, weights::Weight};
+use sp_core::H256;
+use sp_runtime::{
+	Perbill,
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+
+impl_outer_origin! {
+	pub enum Origin for Test {}
+}
+
+// Configure a mock runtime to test the pallet.
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Test;
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl frame_system::Trait for Test {
+	type BaseCallFilter = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+	type AccountData = balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+	pub const MinimumPeriod: u64 = 1;
+}
+
+impl balances::Trait for Test {
+	type Balance = u64;
+	type Event = TestEvent;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = frame_system::Module<Test>;
+	type WeightInfo = ();
+}
+
+impl pallet_timestamp::Trait for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = MinimumPeriod;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const ValidityPeriod: u64 = 10;
+	pub const MaxValidityPeriod: u64 = 20;
+	pub const OperatorBond: u64 = 5;
+	pub const UnbondingPeriod: u64 = 5;
+	pub const MissedRequestSlash: Perbill = Perbill::from_percent(20);
+	pub const RequestHistoryDepth: u32 = 4;
+	pub const CallbackFailurePolicy: chainlink::CallbackFailurePolicy = chainlink::CallbackFailurePolicy::PayOperator;
+	pub const CancellationGracePeriod: u64 = 3;
+	pub const MaxPendingRequestsPerAccount: u32 = 5;
+	pub const MaxPendingRequests: u32 = 8;
+	pub const RequestDeposit: u64 = 0;
+	pub const MaxCallbackWeight: Weight = 1_000_000_000;
+}
+
+impl chainlink::Trait for Test {
+	type Event = TestEvent;
+	type Currency = balances::Module<Test>;
+	type Callback = Call;
+	type ValidityPeriod = ValidityPeriod;
+	type MaxValidityPeriod = MaxValidityPeriod;
+	type OperatorBond = OperatorBond;
+	type UnbondingPeriod = UnbondingPeriod;
+	type MissedRequestSlash = MissedRequestSlash;
+	type SlashOrigin = frame_system::EnsureRoot<u64>;
+	type Slash = ();
+	type HistoryDepth = RequestHistoryDepth;
+	type CallbackFailurePolicy = CallbackFailurePolicy;
+	type CancellationGracePeriod = CancellationGracePeriod;
+	type RegistrationOrigin = frame_system::EnsureRoot<u64>;
+	type MaxPendingRequestsPerAccount = MaxPendingRequestsPerAccount;
+	type MaxPendingRequests = MaxPendingRequests;
+	type RequestDeposit = RequestDeposit;
+	type MaxCallbackWeight = MaxCallbackWeight;
+	type WeightInfo = ();
+}
+
+/// The Oracle configured by the runtime constants
+pub const ORACLE: u64 = 10;
+/// An Oracle configured in storage
+pub const OTHER_ORACLE: u64 = 11;
+/// An account requesting prices
+pub const REQUESTER: u64 = 1;
+pub const JOB_ID: &[u8] = b"pricefeed";
+pub const PRICE_PAIR: &[u8] = b"LINK/USD";
+
+parameter_types! {
+	pub DefaultOracleJobId: Vec<u8> = JOB_ID.to_vec();
+	pub DefaultOracleAccountId: Option<u64> = Some(ORACLE);
+	pub const RefreshInterval: u64 = 10;
+	pub const RefreshFee: u64 = 100;
+	pub const RequestFee: u64 = 50;
+	pub const MaxPricePairs: u32 = 2;
+	pub const PriceDecimals: u8 = 8;
+	pub const PriceHistoryDepth: u32 = 4;
+	pub const MinPrice: i128 = 1;
+	pub const MaxPrice: i128 = 1_000_000_000;
+	pub const MaxPriceDeviation: Perbill = Perbill::from_percent(50);
+}
+
+impl Trait for Test {
+	type Event = TestEvent;
+	type Callback = Call;
+	type DefaultOracleJobId = DefaultOracleJobId;
+	type DefaultOracleAccountId = DefaultOracleAccountId;
+	type RefreshInterval = RefreshInterval;
+	type RefreshFee = RefreshFee;
+	type RequestFee = RequestFee;
+	type MaxPricePairs = MaxPricePairs;
+	type PriceDecimals = PriceDecimals;
+	type PriceHistoryDepth = PriceHistoryDepth;
+	type MinPrice = MinPrice;
+	type MaxPrice = MaxPrice;
+	type MaxPriceDeviation = MaxPriceDeviation;
+	type WeightInfo = ();
+}
+
+mod pricefeed {
+	pub use crate::{Call, Event};
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		frame_system<T>,
+		balances<T>,
+		chainlink<T>,
+		pricefeed,
+	}
+}
+
+impl_outer_dispatch! {
+	pub enum Call for Test where origin: Origin {
+		pricefeed::Pricefeed,
+	}
+}
+
+chainlink::impl_callback_with_parameter!(Call { Pricefeed });
+
+pub type System = frame_system::Module<Test>;
+pub type Balances = balances::Module<Test>;
+pub type Chainlink = chainlink::Module<Test>;
+pub type Pricefeed = Module<Test>;
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	balances::GenesisConfig::<Test> {
+		balances: vec![(REQUESTER, 1_000), (ORACLE, 100), (OTHER_ORACLE, 100)],
+	}.assimilate_storage(&mut t).unwrap();
+	crate::GenesisConfig::<Test>::default().assimilate_storage(&mut t).unwrap();
+	let mut ext: sp_io::TestExternalities = t.into();
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		register_oracle(ORACLE);
+	});
+	ext
+}
+
+/// Register `oracle` with the Chainlink pallet, supporting `JOB_ID`
+pub fn register_oracle(oracle: u64) {
+	Chainlink::register_operator(Origin::signed(oracle)).unwrap();
+	Chainlink::set_specification(Origin::signed(oracle), JOB_ID.to_vec(), 1, vec![RequestParameters::DATA_VERSION]).unwrap();
+}
+
+/// Answer the latest request sent to `oracle` with `price`, with `PriceDecimals` decimals
+pub fn answer(oracle: u64, price: i128) {
+	answer_with_decimals(oracle, price, PriceDecimals::get());
+}
+
+/// Answer the latest request sent to `oracle` with `price`, reported with `decimals` decimals
+pub fn answer_with_decimals(oracle: u64, price: i128, decimals: u8) {
+	let request_id = Chainlink::request_identifier() - 1;
+	chainlink::Call::<Test>::callback(request_id, (price, decimals).encode()).dispatch_bypass_filter(Origin::signed(oracle)).unwrap();
+}
+
+/// The events of this pallet deposited so far
+pub fn pricefeed_events() -> Vec<crate::Event> {
+	System::events().into_iter().filter_map(|record| match record.event {
+		TestEvent::pricefeed(event) => Some(event),
+		_ => None,
+	}).collect()
+}
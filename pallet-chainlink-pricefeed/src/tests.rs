@@ -0,0 +1,210 @@
+use crate::{Error, Event, FixedPrice, GenesisConfig, PriceError, PriceFeeds, mock::*, median, rescale, time_weighted_average};
+use frame_support::{assert_noop, assert_ok, traits::OnInitialize};
+use frame_system::RawOrigin;
+use sp_runtime::{FixedPointNumber, FixedU128, traits::BadOrigin};
+
+#[test]
+fn prices_are_requested_from_the_default_oracle_unless_one_is_configured() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Pricefeed::oracle_account_id(), Some(ORACLE));
+		assert_eq!(Pricefeed::oracle_job_id(), JOB_ID.to_vec());
+		assert_ok!(Pricefeed::request_price(Origin::signed(REQUESTER), PRICE_PAIR.to_vec()));
+		answer(ORACLE, 42);
+		assert_eq!(Pricefeed::latest_price(PRICE_PAIR), 42);
+
+		register_oracle(OTHER_ORACLE);
+		assert_ok!(Pricefeed::set_oracle_configuration(RawOrigin::Root.into(), OTHER_ORACLE, JOB_ID.to_vec()));
+		assert_eq!(Pricefeed::oracle_account_id(), Some(OTHER_ORACLE));
+		assert_ok!(Pricefeed::request_price(Origin::signed(REQUESTER), PRICE_PAIR.to_vec()));
+		answer(OTHER_ORACLE, 43);
+		assert_eq!(Pricefeed::latest_price(PRICE_PAIR), 43);
+		// Requested once, not refreshed
+		assert!(Pricefeed::price_pairs().is_empty());
+		assert_eq!(Balances::free_balance(REQUESTER), 1_000 - 2 * RequestFee::get());
+	});
+}
+
+#[test]
+fn genesis_config_overrides_the_default_oracle() {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	GenesisConfig::<Test> {
+		oracle_account_id: Some(OTHER_ORACLE),
+		oracle_job_id: b"genesis".to_vec(),
+		..Default::default()
+	}.assimilate_storage(&mut t).unwrap();
+	sp_io::TestExternalities::from(t).execute_with(|| {
+		assert_eq!(Pricefeed::oracle_account_id(), Some(OTHER_ORACLE));
+		assert_eq!(Pricefeed::oracle_job_id(), b"genesis".to_vec());
+		assert!(Pricefeed::price_pairs().is_empty());
+	});
+}
+
+#[test]
+fn genesis_config_seeds_a_price() {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	GenesisConfig::<Test> {
+		price_pair: PRICE_PAIR.to_vec(),
+		price: Some(100),
+		..Default::default()
+	}.assimilate_storage(&mut t).unwrap();
+	sp_io::TestExternalities::from(t).execute_with(|| {
+		assert_eq!(Pricefeed::price_pairs(), vec![PRICE_PAIR.to_vec()]);
+		assert_eq!(Pricefeed::latest_price_checked(PRICE_PAIR, 0).map(|price| (price.value, price.answer_count)), Ok((100, 1)));
+		assert_eq!(Pricefeed::price_history(PRICE_PAIR), vec![(0, 100)]);
+	});
+}
+
+#[test]
+fn prices_can_be_requested_from_a_given_operator() {
+	new_test_ext().execute_with(|| {
+		register_oracle(OTHER_ORACLE);
+		assert_ok!(Pricefeed::send_request(Origin::signed(REQUESTER), OTHER_ORACLE, PRICE_PAIR.to_vec()));
+		answer(OTHER_ORACLE, 42);
+		assert_eq!(Pricefeed::latest_price(PRICE_PAIR), 42);
+		assert_eq!(Pricefeed::oracle_account_id(), Some(ORACLE));
+	});
+}
+
+#[test]
+fn price_pairs_are_limited() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(Pricefeed::add_price_pair(Origin::signed(REQUESTER), b"LINK/USD".to_vec()), BadOrigin);
+		assert_ok!(Pricefeed::add_price_pair(RawOrigin::Root.into(), b"LINK/USD".to_vec()));
+		assert_ok!(Pricefeed::add_price_pair(RawOrigin::Root.into(), b"DOT/USD".to_vec()));
+		assert_noop!(Pricefeed::add_price_pair(RawOrigin::Root.into(), b"KSM/USD".to_vec()), Error::<Test>::TooManyPricePairs);
+		assert_ok!(Pricefeed::add_price_pair(RawOrigin::Root.into(), b"LINK/USD".to_vec()));
+		// Requesting a price doesn't add its pair
+		assert_ok!(Pricefeed::request_price(Origin::signed(REQUESTER), b"KSM/USD".to_vec()));
+
+		assert_ok!(Pricefeed::remove_price_pair(RawOrigin::Root.into(), b"DOT/USD".to_vec()));
+		assert_ok!(Pricefeed::add_price_pair(RawOrigin::Root.into(), b"KSM/USD".to_vec()));
+		assert_eq!(Pricefeed::price_pairs(), vec![b"LINK/USD".to_vec(), b"KSM/USD".to_vec()]);
+	});
+}
+
+#[test]
+fn answers_are_stored_with_their_count_and_history() {
+	new_test_ext().execute_with(|| {
+		for (block, price) in (1..=5).zip(100..) {
+			System::set_block_number(block);
+			assert_ok!(Pricefeed::request_price(Origin::signed(REQUESTER), PRICE_PAIR.to_vec()));
+			answer(ORACLE, price);
+		}
+		let price = Pricefeed::get_price(PRICE_PAIR).unwrap();
+		assert_eq!((price.value, price.block_number, price.answer_count, price.decimals), (104, 5, 5, 8));
+		assert_eq!(Pricefeed::price_history(PRICE_PAIR), vec![(2, 101), (3, 102), (4, 103), (5, 104)]);
+
+		assert_eq!(Pricefeed::latest_price_checked(PRICE_PAIR, 0).map(|price| price.value), Ok(104));
+		System::set_block_number(7);
+		assert_eq!(Pricefeed::latest_price_checked(PRICE_PAIR, 1), Err(PriceError::Stale));
+		assert_eq!(Pricefeed::latest_price_checked(b"DOT/USD", 1), Err(PriceError::Missing));
+		assert_eq!(Pricefeed::twap(PRICE_PAIR, 4), FixedPrice::from_raw(103, 8));
+		assert_eq!(Pricefeed::median_price(PRICE_PAIR, 4), FixedPrice::from_raw(103, 8));
+	});
+}
+
+#[test]
+fn averages_are_computed_over_the_window() {
+	let history = [(1u64, 100i128), (5, 200), (9, 400)];
+	// Blocks 2..5 at 100, 5..9 at 200 and 9..10 at 400
+	assert_eq!(time_weighted_average(&history, 10, 8), Some((3 * 100 + 4 * 200 + 400) / 8));
+	assert_eq!(time_weighted_average(&history, 9, 0), Some(400));
+	assert_eq!(time_weighted_average::<u64>(&[], 9, 5), None);
+	assert_eq!(median(&history, 10, 9), Some(200));
+	assert_eq!(median(&history, 10, 5), Some(300));
+	assert_eq!(median(&history, 20, 5), None);
+	// The middle prices don't overflow
+	let max = i128::max_value();
+	assert_eq!(median(&[(1u64, max), (2, max - 2)], 2, 1), Some(max - 1));
+}
+
+#[test]
+fn prices_are_fixed_point_numbers() {
+	assert_eq!(FixedPrice::from_raw(50_000_000, 8).map(|price| price.value), Some(FixedU128::saturating_from_rational(1, 2)));
+	assert_eq!(FixedPrice::from_raw(1_234, 2).map(|price| price.value), Some(FixedU128::saturating_from_rational(1_234, 100)));
+	assert_eq!(FixedPrice::from_raw(-1, 8), None);
+	assert_eq!(FixedPrice::from_raw(1, 40), None);
+
+	new_test_ext().execute_with(|| {
+		assert_eq!(Pricefeed::fixed_price_checked(PRICE_PAIR, 10), Err(PriceError::Missing));
+		assert_ok!(Pricefeed::request_price(Origin::signed(REQUESTER), PRICE_PAIR.to_vec()));
+		answer(ORACLE, 150_000_000);
+		assert_eq!(Pricefeed::fixed_price_checked(PRICE_PAIR, 10), Ok(FixedPrice { value: FixedU128::saturating_from_rational(3, 2), decimals: 8 }));
+	});
+}
+
+#[test]
+fn answers_are_stored_with_the_decimals_reported_by_the_oracle() {
+	assert_eq!(rescale(150, 2, 8), Some(150_000_000));
+	assert_eq!(rescale(-159, 2, 0), Some(-1));
+	assert_eq!(rescale(1, 40, 0), Some(0));
+	assert_eq!(rescale(1, 0, 40), None);
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(Pricefeed::request_price(Origin::signed(REQUESTER), PRICE_PAIR.to_vec()));
+		answer_with_decimals(ORACLE, 150, 2);
+		let price = Pricefeed::get_price(PRICE_PAIR).unwrap();
+		assert_eq!((price.value, price.decimals), (150, 2));
+		assert_eq!(Pricefeed::fixed_price_checked(PRICE_PAIR, 10).map(|price| price.value), Ok(FixedU128::saturating_from_rational(3, 2)));
+
+		// The history and the sanity bounds use `PriceDecimals` decimals
+		assert_ok!(Pricefeed::request_price(Origin::signed(REQUESTER), PRICE_PAIR.to_vec()));
+		answer(ORACLE, 155_000_000);
+		assert_eq!(Pricefeed::price_history(PRICE_PAIR), vec![(1, 150_000_000), (1, 155_000_000)]);
+		assert_ok!(Pricefeed::request_price(Origin::signed(REQUESTER), PRICE_PAIR.to_vec()));
+		answer_with_decimals(ORACLE, i128::max_value(), 0);
+		assert_eq!(Pricefeed::quarantined_price(PRICE_PAIR), Some((1, i128::max_value(), 0)));
+	});
+}
+
+#[test]
+fn insane_answers_are_quarantined_until_governance_decides() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Pricefeed::request_price(Origin::signed(REQUESTER), PRICE_PAIR.to_vec()));
+		answer(ORACLE, 100);
+		// More than `MaxPriceDeviation` away from the previous price
+		assert_ok!(Pricefeed::request_price(Origin::signed(REQUESTER), PRICE_PAIR.to_vec()));
+		answer(ORACLE, 151);
+		assert_eq!(Pricefeed::latest_price(PRICE_PAIR), 100);
+		assert_eq!(Pricefeed::quarantined_price(PRICE_PAIR), Some((1, 151, 8)));
+		assert_eq!(Pricefeed::latest_price_checked(PRICE_PAIR, 10), Err(PriceError::Quarantined));
+		assert_eq!(Pricefeed::twap(PRICE_PAIR, 10), None);
+		assert_eq!(Pricefeed::median_price(PRICE_PAIR, 10), None);
+
+		assert_ok!(Pricefeed::discard_quarantined_price(RawOrigin::Root.into(), PRICE_PAIR.to_vec()));
+		assert_eq!(Pricefeed::latest_price_checked(PRICE_PAIR, 10).map(|price| price.value), Ok(100));
+		assert_eq!(Pricefeed::median_price(PRICE_PAIR, 10), FixedPrice::from_raw(100, 8));
+		assert_noop!(Pricefeed::discard_quarantined_price(RawOrigin::Root.into(), PRICE_PAIR.to_vec()), Error::<Test>::NotQuarantined);
+
+		// Out of the `MinPrice` and `MaxPrice` bounds
+		assert_ok!(Pricefeed::request_price(Origin::signed(REQUESTER), PRICE_PAIR.to_vec()));
+		answer(ORACLE, MaxPrice::get() + 1);
+		assert_ok!(Pricefeed::confirm_quarantined_price(RawOrigin::Root.into(), PRICE_PAIR.to_vec()));
+		assert_eq!(Pricefeed::latest_price_checked(PRICE_PAIR, 10).map(|price| price.value), Ok(MaxPrice::get() + 1));
+
+		assert_eq!(pricefeed_events(), vec![
+			Event::PriceQuarantined(PRICE_PAIR.to_vec(), 151, 8),
+			Event::QuarantinedPriceDiscarded(PRICE_PAIR.to_vec(), 151, 8),
+			Event::PriceQuarantined(PRICE_PAIR.to_vec(), MaxPrice::get() + 1, 8),
+			Event::QuarantinedPriceConfirmed(PRICE_PAIR.to_vec(), MaxPrice::get() + 1, 8),
+		]);
+	});
+}
+
+#[test]
+fn prices_are_refreshed_from_the_pallet_account() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Pricefeed::add_price_pair(RawOrigin::Root.into(), PRICE_PAIR.to_vec()));
+		assert_ok!(Pricefeed::request_price(Origin::signed(REQUESTER), PRICE_PAIR.to_vec()));
+		answer(ORACLE, 100);
+		assert_ok!(Pricefeed::top_up(Origin::signed(REQUESTER), 500));
+
+		Pricefeed::on_initialize(RefreshInterval::get());
+		assert_eq!(Chainlink::pending_requests(&Pricefeed::account_id()), 0);
+
+		let now = 1 + RefreshInterval::get();
+		Pricefeed::on_initialize(now);
+		assert_eq!(Pricefeed::last_refresh(PRICE_PAIR), now);
+		assert_eq!(Chainlink::pending_requests(&Pricefeed::account_id()), 1);
+	});
+}
@@ -0,0 +1,27 @@
+//! Runtime API definition for pallet-chainlink.
+//!
+//! Allows to query the lifecycle of requests, by `RequestIdentifier` or by requester, and the performance of Operators.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::prelude::*;
+
+pub use chainlink::{OperatorStats, RequestIdentifier, RequestRecord, RequestStatus};
+
+sp_api::decl_runtime_apis! {
+	pub trait ChainlinkApi<AccountId, BlockNumber, Balance> where
+		AccountId: Codec,
+		BlockNumber: Codec,
+		Balance: Codec,
+	{
+		// The history of a request, if it is still recorded
+		fn request_status(request_id: RequestIdentifier) -> Option<RequestRecord<AccountId, BlockNumber>>;
+
+		// The history of the most recent requests of a requester, oldest first
+		fn requests_of(requester: AccountId) -> Vec<(RequestIdentifier, RequestRecord<AccountId, BlockNumber>)>;
+
+		// Registered Operators with their performance, best first
+		fn leaderboard() -> Vec<(AccountId, OperatorStats<Balance, BlockNumber>)>;
+	}
+}
@@ -0,0 +1,228 @@
+//! Benchmarks of the extrinsics of the Chainlink pallet
+//!
+//! Benchmarked requests need a callback able to receive a result: the runtime provides one by implementing this module `Trait`,
+//! and benchmarks this pallet through this module `Module`:
+//!
+//! add_benchmark!(params, batches, b"chainlink", ChainlinkBench::<Runtime>);
+
+use super::*;
+use frame_benchmarking::{account, benchmarks};
+use frame_system::RawOrigin;
+use sp_runtime::traits::Bounded;
+
+const SEED: u32 = 0;
+const SPEC_INDEX: &[u8] = b"benchmark";
+// Upper bounds of the benchmarked components, unless the runtime limits are lower
+const MAX_OPERATORS: u32 = 20;
+const MAX_REQUESTS: u32 = 100;
+const MAX_DATA_VERSIONS: u32 = 16;
+
+pub trait Trait: crate::Trait {
+	// A callback accepting a SCALE encoded `i128` result
+	fn callback() -> <Self as crate::Trait>::Callback;
+}
+
+pub struct Module<T: Trait>(crate::Module<T>);
+
+// An account with enough funds to bond and pay any fee
+fn funded_account<T: Trait>(name: &'static str, index: u32) -> T::AccountId {
+	let account: T::AccountId = account(name, index, SEED);
+	T::Currency::make_free_balance_be(&account, BalanceOf::<T>::max_value() / 1_000_000u32.into());
+	account
+}
+
+fn fee<T: Trait>() -> BalanceOf<T> {
+	100u32.into()
+}
+
+// A registered Operator, supporting the benchmarked specification
+fn operator<T: Trait>(index: u32) -> Result<T::AccountId, &'static str> {
+	let operator = funded_account::<T>("operator", index);
+	crate::Module::<T>::register_operator(RawOrigin::Signed(operator.clone()).into())?;
+	crate::Module::<T>::set_specification(RawOrigin::Signed(operator.clone()).into(), SPEC_INDEX.to_vec(), One::one(), vec![1])?;
+	Ok(operator)
+}
+
+// Initiate an aggregated request sent to `o` Operators, all needed to aggregate the result
+// Returns the identifier of the aggregated request and the Operators
+fn aggregated_request<T: Trait>(o: u32) -> Result<(RequestIdentifier, Vec<T::AccountId>), &'static str> {
+	let requester = funded_account::<T>("requester", 0);
+	let operators = (0..o).map(operator::<T>).collect::<Result<Vec<_>, _>>()?;
+	let aggregation_id = crate::Module::<T>::request_identifier();
+	crate::Module::<T>::initiate_aggregated_request(RawOrigin::Signed(requester).into(), operators.clone(), SPEC_INDEX.to_vec(), 1, vec![], fee::<T>(), None, AggregationMode::Median, o, T::callback())?;
+	Ok((aggregation_id, operators))
+}
+
+benchmarks! {
+	_ { }
+
+	register_operator {
+		let caller = funded_account::<T>("caller", 0);
+		// Worst case: registration restricted to approved Operators
+		WhitelistMode::put(true);
+		ApprovedOperators::<T>::insert(&caller, true);
+	}: _(RawOrigin::Signed(caller.clone()))
+	verify {
+		assert!(crate::Module::<T>::operator(&caller));
+	}
+
+	unregister_operator {
+		let caller = operator::<T>(0)?;
+	}: _(RawOrigin::Signed(caller.clone()))
+	verify {
+		assert!(crate::Module::<T>::unbonding(&caller).is_some());
+	}
+
+	set_whitelist_mode {
+		let origin = T::RegistrationOrigin::successful_origin();
+	}: _<T::Origin>(origin, true)
+	verify {
+		assert!(crate::Module::<T>::whitelist_mode());
+	}
+
+	approve_operator {
+		let origin = T::RegistrationOrigin::successful_origin();
+		let operator: T::AccountId = account("operator", 0, SEED);
+	}: _<T::Origin>(origin, operator.clone())
+	verify {
+		assert!(crate::Module::<T>::approved_operator(&operator));
+	}
+
+	remove_operator {
+		let r in 0 .. T::MaxPendingRequests::get().min(MAX_REQUESTS);
+		let operator = operator::<T>(0)?;
+		for index in 0..r {
+			let requester = funded_account::<T>("requester", index);
+			crate::Module::<T>::initiate_request(RawOrigin::Signed(requester).into(), operator.clone(), SPEC_INDEX.to_vec(), 1, vec![], fee::<T>(), None, T::callback())?;
+		}
+		let origin = T::RegistrationOrigin::successful_origin();
+	}: _<T::Origin>(origin, operator.clone(), r)
+	verify {
+		assert!(!crate::Module::<T>::operator(&operator));
+		assert_eq!(crate::Module::<T>::pending_request_count(), 0);
+	}
+
+	withdraw_unbonded {
+		let caller = operator::<T>(0)?;
+		crate::Module::<T>::unregister_operator(RawOrigin::Signed(caller.clone()).into())?;
+		frame_system::Module::<T>::set_block_number(frame_system::Module::<T>::block_number().saturating_add(T::UnbondingPeriod::get()));
+	}: _(RawOrigin::Signed(caller.clone()))
+	verify {
+		assert!(crate::Module::<T>::operator_bond(&caller).is_zero());
+	}
+
+	slash_operator {
+		let operator = operator::<T>(0)?;
+		let origin = T::SlashOrigin::successful_origin();
+	}: _<T::Origin>(origin, operator.clone(), One::one())
+	verify {
+		assert!(crate::Module::<T>::operator_bond(&operator) < T::OperatorBond::get());
+	}
+
+	set_specification {
+		let v in 1 .. MAX_DATA_VERSIONS;
+		let caller = operator::<T>(0)?;
+		let data_versions = (0..v as DataVersion).collect::<Vec<_>>();
+	}: _(RawOrigin::Signed(caller.clone()), SPEC_INDEX.to_vec(), fee::<T>(), data_versions)
+	verify {
+		assert_eq!(crate::Module::<T>::specification(&caller, SPEC_INDEX.to_vec()).map(|specification| specification.data_versions.len()), Some(v as usize));
+	}
+
+	remove_specification {
+		let caller = operator::<T>(0)?;
+	}: _(RawOrigin::Signed(caller.clone()), SPEC_INDEX.to_vec())
+	verify {
+		assert!(crate::Module::<T>::specification(&caller, SPEC_INDEX.to_vec()).is_none());
+	}
+
+	initiate_request {
+		let operator = operator::<T>(0)?;
+		let caller = funded_account::<T>("caller", 0);
+	}: _(RawOrigin::Signed(caller.clone()), operator, SPEC_INDEX.to_vec(), 1, vec![], fee::<T>(), None, T::callback())
+	verify {
+		assert_eq!(crate::Module::<T>::pending_requests(&caller), 1);
+	}
+
+	initiate_aggregated_request {
+		let o in 1 .. T::MaxPendingRequestsPerAccount::get().min(MAX_OPERATORS);
+		let operators = (0..o).map(operator::<T>).collect::<Result<Vec<_>, _>>()?;
+		let caller = funded_account::<T>("caller", 0);
+	}: _(RawOrigin::Signed(caller.clone()), operators, SPEC_INDEX.to_vec(), 1, vec![], fee::<T>(), None, AggregationMode::Median, o, T::callback())
+	verify {
+		assert_eq!(crate::Module::<T>::pending_requests(&caller), o);
+	}
+
+	cancel_request {
+		let o in 1 .. T::MaxPendingRequestsPerAccount::get().min(MAX_OPERATORS);
+		let (aggregation_id, _) = aggregated_request::<T>(o)?;
+		let requester: T::AccountId = account("requester", 0, SEED);
+		frame_system::Module::<T>::set_block_number(frame_system::Module::<T>::block_number().saturating_add(T::CancellationGracePeriod::get()));
+	}: _(RawOrigin::Signed(requester), aggregation_id)
+	verify {
+		assert!(crate::Module::<T>::aggregation(aggregation_id).is_none());
+		assert_eq!(crate::Module::<T>::pending_request_count(), 0);
+	}
+
+	callback {
+		let operator = operator::<T>(0)?;
+		let requester = funded_account::<T>("requester", 0);
+		let request_id = crate::Module::<T>::request_identifier();
+		crate::Module::<T>::initiate_request(RawOrigin::Signed(requester).into(), operator.clone(), SPEC_INDEX.to_vec(), 1, vec![], fee::<T>(), None, T::callback())?;
+	}: _(RawOrigin::Signed(operator), request_id, 0i128.encode())
+	verify {
+		assert_eq!(crate::Module::<T>::operator_stats(&operator).answered, 1);
+	}
+
+	// The last answer needed to aggregate the result of an aggregated request
+	callback_aggregated {
+		let o in 1 .. T::MaxPendingRequestsPerAccount::get().min(MAX_OPERATORS);
+		let (aggregation_id, operators) = aggregated_request::<T>(o)?;
+		let mut sub_requests = operators.into_iter().zip((aggregation_id + 1)..).collect::<Vec<_>>();
+		let (last_operator, last_request) = sub_requests.pop().ok_or("no operator")?;
+		for (operator, request_id) in sub_requests {
+			crate::Module::<T>::callback(RawOrigin::Signed(operator).into(), request_id, 0i128.encode())?;
+		}
+	}: callback(RawOrigin::Signed(last_operator), last_request, 0i128.encode())
+	verify {
+		assert!(crate::Module::<T>::aggregation(aggregation_id).is_none());
+	}
+
+	refund_legacy_request {
+		let requester = funded_account::<T>("requester", 0);
+		T::Currency::reserve(&requester, fee::<T>())?;
+		LegacyRequestFees::<T>::insert(0, fee::<T>());
+	}: _(RawOrigin::Root, 0, requester.clone())
+	verify {
+		assert!(T::Currency::reserved_balance(&requester).is_zero());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tests::{new_test_ext, Runtime};
+	use frame_support::assert_ok;
+
+	#[test]
+	fn benchmarks_run() {
+
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_register_operator::<Runtime>());
+			assert_ok!(test_benchmark_unregister_operator::<Runtime>());
+			assert_ok!(test_benchmark_set_whitelist_mode::<Runtime>());
+			assert_ok!(test_benchmark_approve_operator::<Runtime>());
+			assert_ok!(test_benchmark_remove_operator::<Runtime>());
+			assert_ok!(test_benchmark_withdraw_unbonded::<Runtime>());
+			assert_ok!(test_benchmark_slash_operator::<Runtime>());
+			assert_ok!(test_benchmark_set_specification::<Runtime>());
+			assert_ok!(test_benchmark_remove_specification::<Runtime>());
+			assert_ok!(test_benchmark_initiate_request::<Runtime>());
+			assert_ok!(test_benchmark_initiate_aggregated_request::<Runtime>());
+			assert_ok!(test_benchmark_cancel_request::<Runtime>());
+			assert_ok!(test_benchmark_callback::<Runtime>());
+			assert_ok!(test_benchmark_callback_aggregated::<Runtime>());
+			assert_ok!(test_benchmark_refund_legacy_request::<Runtime>());
+		});
+
+	}
+}
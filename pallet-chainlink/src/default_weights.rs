@@ -0,0 +1,95 @@
+//! Default weights of the extrinsics of the Chainlink pallet, used with `type WeightInfo = ()`
+//!
+//! Database accesses are counted from the worst case of each extrinsic. Execution times should be refreshed
+//! from the `chainlink` benchmarks (see the `benchmarking` module) whenever the extrinsics change.
+
+use frame_support::weights::{Weight, constants::RocksDbWeight as DbWeight};
+
+impl crate::WeightInfo for () {
+	fn register_operator() -> Weight {
+		(62_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(5 as Weight))
+			.saturating_add(DbWeight::get().writes(4 as Weight))
+	}
+	fn unregister_operator() -> Weight {
+		(28_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	fn set_whitelist_mode() -> Weight {
+		(9_000_000 as Weight)
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn approve_operator() -> Weight {
+		(12_000_000 as Weight)
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn remove_operator(r: u32, ) -> Weight {
+		(41_000_000 as Weight)
+			.saturating_add((58_000_000 as Weight).saturating_mul(r as Weight))
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().reads((7 as Weight).saturating_mul(r as Weight)))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+			.saturating_add(DbWeight::get().writes((7 as Weight).saturating_mul(r as Weight)))
+	}
+	fn withdraw_unbonded() -> Weight {
+		(53_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().writes(4 as Weight))
+	}
+	fn slash_operator() -> Weight {
+		(57_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(4 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	fn set_specification(v: u32, ) -> Weight {
+		(31_000_000 as Weight)
+			.saturating_add((150_000 as Weight).saturating_mul(v as Weight))
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn remove_specification() -> Weight {
+		(27_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn initiate_request() -> Weight {
+		(112_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(9 as Weight))
+			.saturating_add(DbWeight::get().writes(10 as Weight))
+	}
+	fn initiate_aggregated_request(o: u32, ) -> Weight {
+		(64_000_000 as Weight)
+			.saturating_add((87_000_000 as Weight).saturating_mul(o as Weight))
+			.saturating_add(DbWeight::get().reads(5 as Weight))
+			.saturating_add(DbWeight::get().reads((3 as Weight).saturating_mul(o as Weight)))
+			.saturating_add(DbWeight::get().writes(5 as Weight))
+			.saturating_add(DbWeight::get().writes((8 as Weight).saturating_mul(o as Weight)))
+	}
+	fn cancel_request(o: u32, ) -> Weight {
+		(38_000_000 as Weight)
+			.saturating_add((36_000_000 as Weight).saturating_mul(o as Weight))
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().reads((4 as Weight).saturating_mul(o as Weight)))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+			.saturating_add(DbWeight::get().writes((7 as Weight).saturating_mul(o as Weight)))
+	}
+	fn callback() -> Weight {
+		(124_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(9 as Weight))
+			.saturating_add(DbWeight::get().writes(8 as Weight))
+	}
+	fn callback_aggregated(o: u32, ) -> Weight {
+		(133_000_000 as Weight)
+			.saturating_add((24_000_000 as Weight).saturating_mul(o as Weight))
+			.saturating_add(DbWeight::get().reads(10 as Weight))
+			.saturating_add(DbWeight::get().reads((3 as Weight).saturating_mul(o as Weight)))
+			.saturating_add(DbWeight::get().writes(9 as Weight))
+			.saturating_add(DbWeight::get().writes((6 as Weight).saturating_mul(o as Weight)))
+	}
+	fn refund_legacy_request() -> Weight {
+		(34_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+}
@@ -0,0 +1,189 @@
+//! Typed helpers to build the `data` of a request and decode the result sent back by an Operator
+//!
+//! Requests built with `RequestParameters` are sent with `RequestParameters::DATA_VERSION`, so that Operators
+//! know how to read them. `RequestParameters::LEGACY_DATA_VERSION` data can still be read. The expected result type is part of the parameters, and the result can then be decoded with `decode_result`.
+
+use codec::{Decode, DecodeAll, Encode, Input};
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+
+use crate::DataVersion;
+
+// Parameter keys understood by Chainlink adapters
+pub const GET: &[u8] = b"get";
+pub const PATH: &[u8] = b"path";
+pub const TIMES: &[u8] = b"times";
+pub const RESULT_TYPE: &[u8] = b"resultType";
+
+// A result type an Operator can provide, SCALE encoded
+pub trait OracleResult: Decode {
+	// The name of the type, as sent to the Operator
+	const RESULT_TYPE: &'static [u8];
+}
+
+impl OracleResult for i128 {
+	const RESULT_TYPE: &'static [u8] = b"int";
+}
+
+impl OracleResult for u128 {
+	const RESULT_TYPE: &'static [u8] = b"uint";
+}
+
+impl OracleResult for [u8; 32] {
+	const RESULT_TYPE: &'static [u8] = b"bytes32";
+}
+
+impl OracleResult for Vec<u8> {
+	const RESULT_TYPE: &'static [u8] = b"string";
+}
+
+impl OracleResult for bool {
+	const RESULT_TYPE: &'static [u8] = b"bool";
+}
+
+// An integer along with its number of decimals, as reported next to the `result` of the adapter
+impl OracleResult for (i128, u8) {
+	const RESULT_TYPE: &'static [u8] = b"price";
+}
+
+// An ordered list of key/value parameters, as used by Chainlink adapters
+// Sent as the SCALE encoded pairs one after the other, without length prefix, as read by the Chainlink external initiator
+#[derive(Clone, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct RequestParameters(pub Vec<(Vec<u8>, Vec<u8>)>);
+
+impl RequestParameters {
+	// The `DataVersion` requests built with `RequestParameters` must be sent with
+	pub const DATA_VERSION: DataVersion = 1;
+	// The `DataVersion` of the parameters encoded as a length prefixed `Vec` of pairs, as sent before `DATA_VERSION`
+	pub const LEGACY_DATA_VERSION: DataVersion = 0;
+
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	// Add any parameter
+	pub fn with(mut self, key: impl AsRef<[u8]>, value: impl AsRef<[u8]>) -> Self {
+		self.0.push((key.as_ref().to_vec(), value.as_ref().to_vec()));
+		self
+	}
+
+	// The URL to fetch
+	pub fn get(self, url: impl AsRef<[u8]>) -> Self {
+		self.with(GET, url)
+	}
+
+	// The JSON path of the value to extract from the response, one key per element
+	pub fn path(self, path: &[&str]) -> Self {
+		let path = path.iter().map(|key| key.as_bytes()).collect::<Vec<_>>().join(&b'.');
+		self.with(PATH, path)
+	}
+
+	// The factor the extracted value is multiplied by
+	pub fn times(self, factor: u128) -> Self {
+		self.with(TIMES, decimal(factor))
+	}
+
+	// The type of the result the Operator must provide
+	pub fn result<R: OracleResult>(self) -> Self {
+		self.with(RESULT_TYPE, R::RESULT_TYPE)
+	}
+
+	// The value of a parameter, if set
+	pub fn value(&self, key: impl AsRef<[u8]>) -> Option<&[u8]> {
+		self.0.iter().find(|(k, _)| k[..] == *key.as_ref()).map(|(_, value)| &value[..])
+	}
+
+	// The `data` to send along `DATA_VERSION` in a request
+	pub fn into_data(self) -> Vec<u8> {
+		self.0.iter().flat_map(|pair| pair.encode()).collect()
+	}
+
+	// The parameters of the `data` of a request sent along `data_version`, either `DATA_VERSION` or `LEGACY_DATA_VERSION`
+	pub fn from_data(data_version: DataVersion, mut data: &[u8]) -> Result<Self, codec::Error> {
+		match data_version {
+			Self::DATA_VERSION => {
+				let mut parameters = vec![];
+				while data.remaining_len()? != Some(0) {
+					parameters.push(Decode::decode(&mut data)?);
+				}
+				Ok(Self(parameters))
+			}
+			Self::LEGACY_DATA_VERSION => Vec::decode_all(data).map(Self),
+			_ => Err("Unsupported data version".into()),
+		}
+	}
+}
+
+// Decode the result of a request, which must be exactly a SCALE encoded `R`
+pub fn decode_result<R: OracleResult>(result: &[u8]) -> Result<R, codec::Error> {
+	R::decode_all(result)
+}
+
+// The decimal representation of a number
+pub(crate) fn decimal(mut value: u128) -> Vec<u8> {
+	let mut digits = vec![];
+	loop {
+		digits.push(b'0' + (value % 10) as u8);
+		value /= 10;
+		if value == 0 {
+			break;
+		}
+	}
+	digits.reverse();
+	digits
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parameters_are_encoded_as_key_values() {
+		let parameters = RequestParameters::new()
+			.get("https://min-api.cryptocompare.com/data/pricemultifull?fsyms=ETH&tsyms=USD")
+			.path(&["RAW", "ETH", "USD", "PRICE"])
+			.times(100000000)
+			.result::<i128>();
+
+		assert_eq!(parameters.value(PATH), Some(&b"RAW.ETH.USD.PRICE"[..]));
+		assert_eq!(parameters.value(TIMES), Some(&b"100000000"[..]));
+		assert_eq!(parameters.value(RESULT_TYPE), Some(&b"int"[..]));
+		assert_eq!(parameters.value("unknown"), None);
+
+		let data = parameters.clone().into_data();
+		assert_eq!(RequestParameters::from_data(RequestParameters::DATA_VERSION, &data), Ok(parameters));
+		assert_eq!(RequestParameters::new().times(0).value(TIMES), Some(&b"0"[..]));
+	}
+
+	#[test]
+	fn parameters_are_sent_in_the_initiator_format() {
+		let data = RequestParameters::new().with("pricePair", "ETH-USD").into_data();
+		assert_eq!(data, ("pricePair", "ETH-USD").encode());
+		assert_eq!(RequestParameters::from_data(RequestParameters::DATA_VERSION, &[]), Ok(RequestParameters::new()));
+		// Truncated pairs are rejected
+		assert!(RequestParameters::from_data(RequestParameters::DATA_VERSION, &data[..data.len() - 1]).is_err());
+		assert!(RequestParameters::from_data(2, &data).is_err());
+	}
+
+	#[test]
+	fn legacy_parameters_are_length_prefixed() {
+		let parameters = RequestParameters::new().with("pricePair", "ETH-USD");
+		let data = parameters.0.encode();
+		assert_ne!(RequestParameters::from_data(RequestParameters::DATA_VERSION, &data), Ok(parameters.clone()));
+		assert_eq!(RequestParameters::from_data(RequestParameters::LEGACY_DATA_VERSION, &data), Ok(parameters));
+	}
+
+	#[test]
+	fn results_are_decoded() {
+		assert_eq!(decode_result::<i128>(&(-42i128).encode()), Ok(-42));
+		assert_eq!(decode_result::<u128>(&42u128.encode()), Ok(42));
+		assert_eq!(decode_result::<[u8; 32]>(&[1u8; 32].encode()), Ok([1u8; 32]));
+		assert_eq!(decode_result::<Vec<u8>>(&b"ETH".to_vec().encode()), Ok(b"ETH".to_vec()));
+		assert_eq!(decode_result::<bool>(&true.encode()), Ok(true));
+		assert_eq!(decode_result::<(i128, u8)>(&(-42i128, 8u8).encode()), Ok((-42, 8)));
+
+		// Truncated or trailing bytes are rejected
+		assert!(decode_result::<i128>(&42u64.encode()).is_err());
+		assert!(decode_result::<bool>(&[1, 0]).is_err());
+	}
+}
@@ -9,23 +9,77 @@
 //!
 //! To be valid, an operator must register its AccountId first hand via `register_operator`.
 //!
+//! The `encoding` module helps building the `data` of a request and decoding its result, in a format identified by its `DataVersion`.
+//!
+//! With the `offchain-operator` feature, the `offchain` module provides an Operator running as an offchain worker, answering requests through an external adapter.
+//!
+//! With the `runtime-benchmarks` feature, the `benchmarking` module benchmarks every extrinsic, so that the runtime can provide its own `WeightInfo`.
+//!
+//! To avoid relying on a single operator, users can call `initiate_aggregated_request` instead. The request is then fanned out to several operators
+//! and their answers are aggregated (median, mean or quorum) before being dispatched to the callback.
+//!
 //! \## Terminology
 //! Operator: a member of chainlink that provides result to requests, in exchange of a fee payment
 //! Request: details about what the user expects as result. Must match a Specification supported by an identified Operator
 //! Fee: the amount of token a users pays to an operator
+//! Aggregated request: a request sent to several Operators, whose answers are combined into a single result
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
 #[warn(unused_imports)]
-use codec::Codec;
-use frame_support::{decl_error, decl_event, decl_module, decl_storage, ensure, Parameter, dispatch::DispatchResult};
-use frame_support::traits::{Get, ReservableCurrency, BalanceStatus, UnfilteredDispatchable};
+use codec::{Codec, Decode, Encode};
+use frame_support::{decl_error, decl_event, decl_module, decl_storage, ensure, transactional, Parameter, dispatch::{DispatchError, DispatchResult}, weights::{GetDispatchInfo, Weight}};
+use frame_support::storage::migration::{put_storage_value, StorageIterator};
+use frame_support::traits::{Currency, EnsureOrigin, Get, Imbalance, OnUnbalanced, ReservableCurrency, BalanceStatus, UnfilteredDispatchable};
+use sp_runtime::{Perbill, RuntimeDebug, traits::{AtLeast32BitUnsigned, One, Saturating, Zero}};
 use sp_std::prelude::*;
-use frame_system::ensure_signed;
+use frame_system::{ensure_root, ensure_signed};
+
+pub mod encoding;
+#[cfg(feature = "offchain-operator")]
+pub mod offchain;
+#[cfg(feature = "runtime-benchmarks")]
+pub mod benchmarking;
+mod default_weights;
 
 // A trait allowing to inject Operator results back into the specified Call
 pub trait CallbackWithParameter {
 	fn with_result(&self, result: Vec<u8>) -> Option<Self> where Self: core::marker::Sized;
+
+	// The Call to dispatch when the request didn't get any result, if the originator wants to be notified
+	fn on_failure(&self) -> Option<Self> where Self: core::marker::Sized {
+		None
+	}
+}
+
+#[doc(hidden)]
+pub use sp_std;
+
+// Implement `CallbackWithParameter` for the runtime `Call`, delegating to the Calls of the listed pallets.
+// This allows any of these pallets to receive Chainlink results, using the runtime `Call` as `Trait::Callback`:
+//
+// impl_callback_with_parameter!(Call { Pricefeed, KidotLoan });
+#[macro_export]
+macro_rules! impl_callback_with_parameter {
+	($call:ty { $( $pallet:ident ),* $(,)? }) => {
+		impl $crate::CallbackWithParameter for $call {
+			fn with_result(&self, result: $crate::sp_std::vec::Vec<u8>) -> Option<Self> {
+				#[allow(unreachable_patterns)]
+				match self {
+					$( Self::$pallet(call) => $crate::CallbackWithParameter::with_result(call, result).map(Self::$pallet), )*
+					_ => None,
+				}
+			}
+
+			fn on_failure(&self) -> Option<Self> {
+				#[allow(unreachable_patterns)]
+				match self {
+					$( Self::$pallet(call) => $crate::CallbackWithParameter::on_failure(call).map(Self::$pallet), )*
+					_ => None,
+				}
+			}
+		}
+	}
 }
 
 pub trait Trait: frame_system::Trait {
@@ -33,10 +87,82 @@ pub trait Trait: frame_system::Trait {
 	type Currency: ReservableCurrency<Self::AccountId>;
 
 	// A reference to an Extrinsic that can have a result injected. Used as Chainlink callback
-	type Callback: Parameter + UnfilteredDispatchable<Origin = Self::Origin> + Codec + Eq + CallbackWithParameter;
+	// Usually the runtime `Call`, see `impl_callback_with_parameter`, so that any pallet can receive results
+	type Callback: Parameter + UnfilteredDispatchable<Origin = Self::Origin> + GetDispatchInfo + Codec + Eq + CallbackWithParameter;
 
-	// Period during which a request is valid
+	// Period during which a request is valid, starting from the block following the one the request was initiated in
+	// Used unless the requester provides its own validity period
 	type ValidityPeriod: Get<Self::BlockNumber>;
+
+	// Longest validity period a requester can ask for
+	type MaxValidityPeriod: Get<Self::BlockNumber>;
+
+	// Amount reserved from an Operator while it is registered
+	type OperatorBond: Get<BalanceOf<Self>>;
+
+	// Number of blocks an unregistered Operator has to wait before getting its bond back
+	type UnbondingPeriod: Get<Self::BlockNumber>;
+
+	// Portion of its bond an Operator loses for each request it let expire
+	type MissedRequestSlash: Get<Perbill>;
+
+	// Origin allowed to slash an Operator
+	type SlashOrigin: EnsureOrigin<Self::Origin>;
+
+	// Handler for the funds slashed from Operators
+	type Slash: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+	// Number of requests whose history is kept for each requester. Older records are pruned
+	type HistoryDepth: Get<u32>;
+
+	// What happens to a request whose result couldn't be dispatched to its callback
+	type CallbackFailurePolicy: Get<CallbackFailurePolicy>;
+
+	// Number of blocks after its initiation during which a request can't be cancelled, leaving Operators time to answer
+	type CancellationGracePeriod: Get<Self::BlockNumber>;
+
+	// Origin allowed to approve Operators when `WhitelistMode` is enabled, and to forcibly remove them
+	type RegistrationOrigin: EnsureOrigin<Self::Origin>;
+
+	// Maximum number of requests an account can have pending at the same time. Each sub request of an aggregated request counts
+	type MaxPendingRequestsPerAccount: Get<u32>;
+
+	// Maximum number of requests pending at the same time, all accounts included
+	type MaxPendingRequests: Get<u32>;
+
+	// Amount reserved from the requester for each pending request, given back once the request is removed
+	type RequestDeposit: Get<BalanceOf<Self>>;
+
+	// Maximum weight of the Calls dispatched with a result or to notify a failure, as their weight is charged to the Operator
+	// Requests with heavier callbacks are refused, and results making them heavier are not dispatched
+	type MaxCallbackWeight: Get<Weight>;
+
+	// Weights of the extrinsics, `()` provides default ones
+	type WeightInfo: WeightInfo;
+}
+
+// Weights of the extrinsics of this pallet
+pub trait WeightInfo {
+	fn register_operator() -> Weight;
+	fn unregister_operator() -> Weight;
+	fn set_whitelist_mode() -> Weight;
+	fn approve_operator() -> Weight;
+	// `r`: running requests
+	fn remove_operator(r: u32) -> Weight;
+	fn withdraw_unbonded() -> Weight;
+	fn slash_operator() -> Weight;
+	// `v`: supported data versions
+	fn set_specification(v: u32) -> Weight;
+	fn remove_specification() -> Weight;
+	fn initiate_request() -> Weight;
+	// `o`: Operators of the aggregated request
+	fn initiate_aggregated_request(o: u32) -> Weight;
+	// `o`: running sub requests of the aggregated request
+	fn cancel_request(o: u32) -> Weight;
+	fn callback() -> Weight;
+	// `o`: Operators of the aggregated request
+	fn callback_aggregated(o: u32) -> Weight;
+	fn refund_legacy_request() -> Weight;
 }
 
 // Uniquely identify a request's specification understood by an Operator
@@ -46,35 +172,368 @@ pub type RequestIdentifier = u64;
 // The version of the serialized data format
 pub type DataVersion = u64;
 
+// The balance type used to pay fees and bond Operators
+pub type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::NegativeImbalance;
+
+// Details of a running request
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Request<AccountId, Callback, BlockNumber, Balance> {
+	// The Operator expected to provide the result
+	pub operator: AccountId,
+	// The account that initiated the request and reserved the fee
+	pub requester: AccountId,
+	pub callback: Callback,
+	// The block at which the request has been initiated
+	pub block_number: BlockNumber,
+	pub fee: Balance,
+	// The last block in which a result is accepted
+	pub deadline: BlockNumber,
+}
+
+// A request as stored up to `Releases::V4`, before deadlines were introduced
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+struct OldRequest<AccountId, Callback, BlockNumber, Balance> {
+	operator: AccountId,
+	requester: AccountId,
+	callback: Callback,
+	block_number: BlockNumber,
+	fee: Balance,
+}
+
+// A storage value read as its raw bytes, whatever their encoding
+struct RawValue(Vec<u8>);
+
+impl Decode for RawValue {
+	fn decode<I: codec::Input>(input: &mut I) -> Result<Self, codec::Error> {
+		let mut value = vec![0; input.remaining_len()?.unwrap_or(0)];
+		input.read(&mut value)?;
+		Ok(RawValue(value))
+	}
+}
+
+// Details of a specification supported by an Operator
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Default, RuntimeDebug)]
+pub struct Specification<Balance> {
+	// Minimum fee accepted by the Operator
+	pub min_fee: Balance,
+	// Data formats understood by the Operator
+	pub data_versions: Vec<DataVersion>,
+}
+
+// The stage of its lifecycle a request reached, along with the block it reached it in
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum RequestStatus<BlockNumber> {
+	// Waiting for an answer
+	Pending,
+	// Answered, and the result successfully dispatched to the callback (if any)
+	Answered(BlockNumber),
+	// No answer received in time
+	Expired(BlockNumber),
+	// Answered, but dispatching the result to the callback failed
+	CallbackFailed(BlockNumber),
+	// Sub request not needed anymore, its aggregated request being already answered
+	Dropped(BlockNumber),
+	// Aggregated request whose answers couldn't be aggregated
+	AggregationFailed(BlockNumber),
+	// Cancelled by the requester
+	Cancelled(BlockNumber),
+}
+
+// How a request is settled when dispatching its result to the callback fails
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum CallbackFailurePolicy {
+	// The Operator is paid anyway, as for a successful dispatch
+	PayOperator,
+	// The fee is given back to the requester
+	RefundRequester,
+	// The request keeps running, so that the Operator can provide another result before it expires
+	KeepOpen,
+}
+
+// History of a request, kept after the request itself is gone
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct RequestRecord<AccountId, BlockNumber> {
+	pub requester: AccountId,
+	// `None` for aggregated requests, each of their sub requests being recorded with its own Operator
+	pub operator: Option<AccountId>,
+	// The block the request was initiated in
+	pub block_number: BlockNumber,
+	pub status: RequestStatus<BlockNumber>,
+}
+
+// Performance of an Operator since it first got a request
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct OperatorStats<Balance, BlockNumber> {
+	// Requests sent to the Operator
+	pub assigned: u32,
+	// Requests answered by the Operator
+	pub answered: u32,
+	// Requests the Operator let expire
+	pub expired: u32,
+	// Sum of the blocks elapsed between each request and its answer
+	pub total_latency: BlockNumber,
+	// Fees received for the answered requests
+	pub earned: Balance,
+}
+
+impl<Balance, BlockNumber: AtLeast32BitUnsigned + Copy> OperatorStats<Balance, BlockNumber> {
+	// Average number of blocks the Operator takes to answer
+	pub fn mean_latency(&self) -> Option<BlockNumber> {
+		if self.answered == 0 {
+			None
+		} else {
+			Some(self.total_latency / self.answered.into())
+		}
+	}
+
+	// Whether the Operator performs better than another one: higher answer rate first, then lower mean latency
+	pub fn ranks_before(&self, other: &Self) -> bool {
+		// Compare `answered / assigned` ratios without dividing
+		let rate = u64::from(self.answered) * u64::from(other.assigned.max(1));
+		let other_rate = u64::from(other.answered) * u64::from(self.assigned.max(1));
+		if rate != other_rate {
+			return rate > other_rate;
+		}
+		match (self.mean_latency(), other.mean_latency()) {
+			(Some(latency), Some(other_latency)) if latency != other_latency => latency < other_latency,
+			_ => self.answered > other.answered,
+		}
+	}
+}
+
+// Storage layouts of the pallet, used to migrate storage on runtime upgrades
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum Releases {
+	// Requests stored as `(operator, callbacks, block_number, fee)` tuples
+	V1,
+	// Requests stored as `Request`, including the requester
+	V2,
+	// Running requests indexed by expiry block in `ExpiringRequests`
+	V3,
+	// Fees stored as `BalanceOf<T>` instead of `u32`
+	V4,
+	// Requests stored with their own deadline
+	V5,
+	// Pending requests counted in `PendingRequests` and `PendingRequestCount`
+	V6,
+}
+
+impl Default for Releases {
+	fn default() -> Self {
+		Releases::V1
+	}
+}
+
+// How the answers of the Operators of an aggregated request are combined
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum AggregationMode {
+	// The median of the answers, each one being a SCALE encoded `i128`
+	Median,
+	// The mean of the answers, each one being a SCALE encoded `i128`
+	Mean,
+	// An answer provided identically by at least `threshold` Operators
+	Quorum,
+}
+
+// Details of a request fanned out to several Operators
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Aggregation<AccountId> {
+	pub mode: AggregationMode,
+	// Number of answers required before the aggregated result is dispatched
+	pub threshold: u32,
+	// Sub requests (one per Operator) still waiting for an answer
+	pub pending: Vec<RequestIdentifier>,
+	// Answers received so far
+	pub answers: Vec<(AccountId, Vec<u8>)>,
+}
+
+impl<AccountId> Aggregation<AccountId> {
+	// The aggregated result, if enough answers have been received
+	pub fn aggregated_result(&self) -> Option<Vec<u8>> {
+		let threshold = self.threshold as usize;
+		if self.answers.len() < threshold {
+			return None;
+		}
+		match self.mode {
+			AggregationMode::Quorum => self.answers.iter()
+				.map(|(_, answer)| answer)
+				.find(|answer| self.answers.iter().filter(|(_, other)| other == *answer).count() >= threshold)
+				.cloned(),
+			AggregationMode::Median | AggregationMode::Mean => {
+				let mut values: Vec<i128> = self.answers.iter()
+					.filter_map(|(_, answer)| encoding::decode_result::<i128>(answer).ok())
+					.collect();
+				if values.is_empty() {
+					return None;
+				}
+				let value = if self.mode == AggregationMode::Median {
+					median(&mut values)?
+				} else {
+					values.iter().fold(0i128, |sum, value| sum.saturating_add(*value)) / values.len() as i128
+				};
+				Some(value.encode())
+			}
+		}
+	}
+}
+
+// The median of `values`, or the mean of the two middle values (rounded towards zero) for an even number of values
+pub fn median(values: &mut [i128]) -> Option<i128> {
+	if values.is_empty() {
+		return None;
+	}
+	values.sort();
+	let middle = values.len() / 2;
+	if values.len() % 2 == 0 {
+		let (low, high) = (values[middle - 1], values[middle]);
+		Some(low / 2 + high / 2 + (low % 2 + high % 2) / 2)
+	} else {
+		Some(values[middle])
+	}
+}
+
 decl_storage! {
     trait Store for Module<T: Trait> as Chainlink {
 		// A set of all registered Operator
 		pub Operators get(fn operator): map hasher(twox_64_concat) T::AccountId => bool;
 
+		// Whether only approved Operators can register. Registration is open otherwise
+		pub WhitelistMode get(fn whitelist_mode) config(): bool;
+
+		// The Operators `RegistrationOrigin` allowed to register while `WhitelistMode` is enabled
+		pub ApprovedOperators get(fn approved_operator): map hasher(twox_64_concat) T::AccountId => bool;
+
+		// The performance of each Operator
+		pub OperatorStatistics get(fn operator_stats): map hasher(twox_64_concat) T::AccountId => OperatorStats<BalanceOf<T>, T::BlockNumber>;
+
+		// The bond reserved from each Operator, registered or unbonding
+		pub OperatorBonds get(fn operator_bond): map hasher(twox_64_concat) T::AccountId => BalanceOf<T>;
+
+		// The block from which each unregistered Operator can withdraw its bond
+		pub Unbonding get(fn unbonding): map hasher(twox_64_concat) T::AccountId => Option<T::BlockNumber>;
+
+		// The specifications supported by each Operator
+		pub Specifications get(fn specification): double_map hasher(twox_64_concat) T::AccountId, hasher(blake2_128_concat) SpecIndex => Option<Specification<BalanceOf<T>>>;
+
 		// A running counter used internally to identify the next request
 		pub NextRequestIdentifier get(fn request_identifier): RequestIdentifier;
 
 		// A map of details of each running request
-		pub Requests get(fn request): map hasher(twox_64_concat) RequestIdentifier => (T::AccountId, Vec<T::Callback>, T::BlockNumber, u32);
+		pub Requests get(fn request): map hasher(twox_64_concat) RequestIdentifier => Option<Request<T::AccountId, T::Callback, T::BlockNumber, BalanceOf<T>>>;
+
+		// The deposit reserved from the requester of each running request
+		pub RequestDeposits get(fn request_deposit): map hasher(twox_64_concat) RequestIdentifier => BalanceOf<T>;
+
+		// The number of running requests of each requester
+		pub PendingRequests get(fn pending_requests): map hasher(twox_64_concat) T::AccountId => u32;
+
+		// The number of running requests, all requesters included
+		pub PendingRequestCount get(fn pending_request_count): u32;
+
+		// The running requests of each Operator, cancelled if it is removed
+		pub OperatorRequests get(fn operator_request): double_map hasher(twox_64_concat) T::AccountId, hasher(twox_64_concat) RequestIdentifier => bool;
+
+		// Running requests kept open by `CallbackFailurePolicy::KeepOpen`, although their Operator answered in time
+		pub AnsweredRequests get(fn answered_request): map hasher(twox_64_concat) RequestIdentifier => bool;
+
+		// Requests to kill at the beginning of a block, if they are still running by then
+		pub ExpiringRequests get(fn expiring_requests): map hasher(twox_64_concat) T::BlockNumber => Vec<RequestIdentifier>;
+
+		// A map of details of each running aggregated request
+		pub Aggregations get(fn aggregation): map hasher(twox_64_concat) RequestIdentifier => Option<Aggregation<T::AccountId>>;
+
+		// The aggregated request each running sub request belongs to
+		pub AggregatedRequestOf get(fn aggregated_request_of): map hasher(twox_64_concat) RequestIdentifier => Option<RequestIdentifier>;
+
+		// The history of the most recent requests, answered or not
+		pub RequestHistory get(fn request_record): map hasher(twox_64_concat) RequestIdentifier => Option<RequestRecord<T::AccountId, T::BlockNumber>>;
+
+		// The requests recorded in `RequestHistory` for each requester, oldest first
+		pub RequesterHistory get(fn requester_history): map hasher(twox_64_concat) T::AccountId => Vec<RequestIdentifier>;
+
+		// The fee still reserved for each request killed by the `Releases::V2` migration, until it is refunded
+		pub LegacyRequestFees get(fn legacy_request_fee): map hasher(twox_64_concat) RequestIdentifier => Option<BalanceOf<T>>;
+
+		// The storage layout currently in use
+		StorageVersion build(|_: &GenesisConfig<T>| Releases::V6): Releases;
     }
+	add_extra_genesis {
+		config(approved_operators): Vec<T::AccountId>;
+		// Operators registered from the first block, bonding `OperatorBond`, along with their specifications
+		config(operators): Vec<(T::AccountId, Vec<(SpecIndex, BalanceOf<T>, Vec<DataVersion>)>)>;
+		build(|config: &GenesisConfig<T>| {
+			for operator in &config.approved_operators {
+				ApprovedOperators::<T>::insert(operator, true);
+			}
+			for (operator, specifications) in &config.operators {
+				let bond = T::OperatorBond::get();
+				T::Currency::reserve(operator, bond).expect("Genesis Operators can afford OperatorBond");
+				OperatorBonds::<T>::insert(operator, bond);
+				Operators::<T>::insert(operator, true);
+				for (spec_index, min_fee, data_versions) in specifications {
+					Specifications::<T>::insert(operator, spec_index, Specification { min_fee: *min_fee, data_versions: data_versions.clone() });
+				}
+			}
+		});
+	}
 }
 
 decl_event!(
-	pub enum Event<T> where AccountId = <T as frame_system::Trait>::AccountId {
+	pub enum Event<T> where AccountId = <T as frame_system::Trait>::AccountId, Balance = BalanceOf<T> {
 		// A request has been accepted. Corresponding fee paiement is reserved
-		OracleRequest(AccountId, SpecIndex, RequestIdentifier, AccountId, DataVersion, Vec<u8>, Vec<u8>, u32),
+		OracleRequest(AccountId, SpecIndex, RequestIdentifier, AccountId, DataVersion, Vec<u8>, Vec<u8>, Balance),
 
 		// A request has been answered. Corresponding fee paiement is transfered
-		OracleAnswer(AccountId, RequestIdentifier, AccountId, Vec<u8>, u32),
+		OracleAnswer(AccountId, RequestIdentifier, AccountId, Vec<u8>, Balance),
 
 		// A new operator has been registered
 		OperatorRegistered(AccountId),
 
-		// An existing operator has been unregistered
+		// An existing operator has been unregistered. Its bond will be withdrawable after the unbonding period
 		OperatorUnregistered(AccountId),
 
+		// An operator has been approved, and can register while the whitelist mode is enabled
+		OperatorApproved(AccountId),
+
+		// An operator has been forcibly removed. Its pending requests have been cancelled and its bond will be withdrawable after the unbonding period
+		OperatorRemoved(AccountId),
+
+		// Registration has been restricted to approved operators (true), or opened to anyone (false)
+		WhitelistModeSet(bool),
+
+		// An unregistered operator got its bond back
+		BondWithdrawn(AccountId, Balance),
+
+		// Part of an operator bond has been slashed
+		OperatorSlashed(AccountId, Balance),
+
+		// An operator published a specification it supports, or updated it
+		SpecificationSet(AccountId, SpecIndex),
+
+		// An operator doesn't support a specification anymore
+		SpecificationRemoved(AccountId, SpecIndex),
+
 		// A request didn't receive any result in time
 		KillRequest(RequestIdentifier),
+
+		// The result of a request couldn't be dispatched to its callback
+		CallbackFailed(RequestIdentifier, DispatchError),
+
+		// A request has been cancelled by its requester. Corresponding fee paiement is unreserved
+		RequestCancelled(RequestIdentifier),
+
+		// A request has been fanned out to several operators, one sub request per operator
+		AggregatedOracleRequest(RequestIdentifier, AccountId, Vec<RequestIdentifier>, AggregationMode, u32),
+
+		// Enough operators answered an aggregated request. The aggregated result has been dispatched
+		AggregatedOracleAnswer(RequestIdentifier, Vec<u8>),
+
+		// An aggregated request didn't receive enough answers to be aggregated
+		AggregationFailed(RequestIdentifier),
+
+		// The fee of a request killed on upgrade has been unreserved from its requester
+		LegacyRequestRefunded(RequestIdentifier, AccountId, Balance),
 	}
 );
 
@@ -89,10 +548,44 @@ decl_error! {
 		WrongOperator,
 		// An operator is already registered.
 		OperatorAlreadyRegistered,
+		// Registration is restricted to approved operators
+		NotApproved,
 		// Callback cannot be deserialized
 		UnknownCallback,
 		// Fee provided does not match minimum required fee
 		InsufficientFee,
+		// Threshold must be between 1 and the number of operators
+		InvalidThreshold,
+		// The same operator is used twice in an aggregated request
+		DuplicateOperator,
+		// Result cannot be aggregated with the other answers
+		InvalidAggregatedResult,
+		// Operator is not unbonding
+		NotUnbonding,
+		// Unbonding period is not over yet
+		UnbondingInProgress,
+		// Specification is not supported by the operator
+		UnknownSpecification,
+		// Data version is not supported by the operator for this specification
+		UnsupportedDataVersion,
+		// Only the requester can cancel a request
+		NotRequester,
+		// The request is still in its cancellation grace period
+		CancellationGracePeriod,
+		// Sub requests are cancelled along with their aggregated request only
+		SubRequestNotCancellable,
+		// Validity period must be between 1 and `MaxValidityPeriod`
+		InvalidValidityPeriod,
+		// The deadline of the request has passed
+		DeadlinePassed,
+		// The `pending` witness is lower than the number of pending requests of the Operator
+		InvalidPendingWitness,
+		// The requester reached `MaxPendingRequestsPerAccount`
+		TooManyPendingRequests,
+		// `MaxPendingRequests` are already running
+		PendingRequestsLimitReached,
+		// The callback is heavier than `MaxCallbackWeight`
+		CallbackTooHeavy,
 	}
 }
 
@@ -101,13 +594,35 @@ decl_module! {
 
 		fn deposit_event() = default;
 
+		const OperatorBond: BalanceOf<T> = T::OperatorBond::get();
+		const UnbondingPeriod: T::BlockNumber = T::UnbondingPeriod::get();
+		const HistoryDepth: u32 = T::HistoryDepth::get();
+		const CancellationGracePeriod: T::BlockNumber = T::CancellationGracePeriod::get();
+		const ValidityPeriod: T::BlockNumber = T::ValidityPeriod::get();
+		const MaxValidityPeriod: T::BlockNumber = T::MaxValidityPeriod::get();
+		const MaxPendingRequestsPerAccount: u32 = T::MaxPendingRequestsPerAccount::get();
+		const MaxPendingRequests: u32 = T::MaxPendingRequests::get();
+		const RequestDeposit: BalanceOf<T> = T::RequestDeposit::get();
+		const MaxCallbackWeight: Weight = T::MaxCallbackWeight::get();
+
 		// Register a new Operator.
 		// Fails with `OperatorAlreadyRegistered` if this Operator (identified by `origin`) has already been registered.
-		#[weight = 0]
+		// While `WhitelistMode` is enabled, fails with `NotApproved` unless the Operator has been approved by `RegistrationOrigin`.
+		// `OperatorBond` is reserved from the Operator. An unbonding Operator only has to top up its remaining bond.
+		#[weight = T::WeightInfo::register_operator()]
 		pub fn register_operator(origin) -> DispatchResult {
 			let who : <T as frame_system::Trait>::AccountId = ensure_signed(origin)?;
 
 			ensure!(!<Operators<T>>::contains_key(who.clone()), Error::<T>::OperatorAlreadyRegistered);
+			ensure!(!Self::whitelist_mode() || Self::approved_operator(&who), Error::<T>::NotApproved);
+
+			let bond = Self::operator_bond(&who);
+			let required_bond = T::OperatorBond::get();
+			if bond < required_bond {
+				T::Currency::reserve(&who, required_bond - bond)?;
+				OperatorBonds::<T>::insert(&who, required_bond);
+			}
+			Unbonding::<T>::remove(&who);
 
 			Operators::<T>::insert(&who, true);
 
@@ -117,11 +632,14 @@ decl_module! {
 		}
 
 		// Unregisters an existing Operator
-		#[weight = 0]
+		// Its bond stays reserved during `UnbondingPeriod`, so it can still be slashed for the requests it let expire.
+		#[weight = T::WeightInfo::unregister_operator()]
 		pub fn unregister_operator(origin) -> DispatchResult {
 			let who : <T as frame_system::Trait>::AccountId = ensure_signed(origin)?;
 
 			if Operators::<T>::take(who.clone()) {
+				let now = frame_system::Module::<T>::block_number();
+				Unbonding::<T>::insert(&who, now.saturating_add(T::UnbondingPeriod::get()));
 				Self::deposit_event(RawEvent::OperatorUnregistered(who));
 				Ok(())
 			} else {
@@ -129,142 +647,832 @@ decl_module! {
 			}
 		}
 
+		// Restrict registration to the Operators approved by `RegistrationOrigin`, or open it to anyone
+		// Already registered Operators are not affected.
+		#[weight = T::WeightInfo::set_whitelist_mode()]
+		pub fn set_whitelist_mode(origin, enabled: bool) -> DispatchResult {
+			T::RegistrationOrigin::ensure_origin(origin)?;
+
+			WhitelistMode::put(enabled);
+
+			Self::deposit_event(RawEvent::WhitelistModeSet(enabled));
+			Ok(())
+		}
+
+		// Allow an Operator to register while `WhitelistMode` is enabled
+		#[weight = T::WeightInfo::approve_operator()]
+		pub fn approve_operator(origin, operator: T::AccountId) -> DispatchResult {
+			T::RegistrationOrigin::ensure_origin(origin)?;
+
+			ApprovedOperators::<T>::insert(&operator, true);
+
+			Self::deposit_event(RawEvent::OperatorApproved(operator));
+			Ok(())
+		}
+
+		// Forcibly unregister an Operator, and revoke its approval
+		// Its pending requests are cancelled and their fees given back to the requesters.
+		// Its bond stays reserved during `UnbondingPeriod`, so it can still be slashed.
+		// `pending` must be at least the number of pending requests of the Operator, which the call is weighed on.
+		#[weight = T::WeightInfo::remove_operator(*pending)]
+		pub fn remove_operator(origin, operator: T::AccountId, pending: u32) -> DispatchResult {
+			T::RegistrationOrigin::ensure_origin(origin)?;
+
+			ensure!(Self::operator(&operator), Error::<T>::UnknownOperator);
+			let request_ids = OperatorRequests::<T>::iter_prefix(&operator)
+				.map(|(request_id, _)| request_id)
+				.take((pending as usize).saturating_add(1))
+				.collect::<Vec<_>>();
+			ensure!(request_ids.len() <= pending as usize, Error::<T>::InvalidPendingWitness);
+
+			Operators::<T>::remove(&operator);
+			ApprovedOperators::<T>::remove(&operator);
+
+			let now = frame_system::Module::<T>::block_number();
+			Unbonding::<T>::insert(&operator, now.saturating_add(T::UnbondingPeriod::get()));
+
+			for request_id in request_ids {
+				if let Some(request) = Self::request(request_id) {
+					Self::cancel_operator_request(request_id, request);
+				}
+			}
+
+			Self::deposit_event(RawEvent::OperatorRemoved(operator));
+			Ok(())
+		}
+
+		// Give its bond back to an unregistered Operator, once the unbonding period is over
+		#[weight = T::WeightInfo::withdraw_unbonded()]
+		pub fn withdraw_unbonded(origin) -> DispatchResult {
+			let who : <T as frame_system::Trait>::AccountId = ensure_signed(origin)?;
+
+			let unbonded_at = Self::unbonding(&who).ok_or(Error::<T>::NotUnbonding)?;
+			ensure!(frame_system::Module::<T>::block_number() >= unbonded_at, Error::<T>::UnbondingInProgress);
+
+			Unbonding::<T>::remove(&who);
+			Specifications::<T>::remove_prefix(&who);
+			let bond = OperatorBonds::<T>::take(&who);
+			T::Currency::unreserve(&who, bond);
+
+			Self::deposit_event(RawEvent::BondWithdrawn(who, bond));
+			Ok(())
+		}
+
+		// Slash up to `amount` from the bond of an Operator, registered or unbonding
+		#[weight = T::WeightInfo::slash_operator()]
+		pub fn slash_operator(origin, operator: T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
+			T::SlashOrigin::ensure_origin(origin)?;
+
+			ensure!(<Operators<T>>::contains_key(&operator) || <Unbonding<T>>::contains_key(&operator), Error::<T>::UnknownOperator);
+
+			Self::slash_bond(&operator, amount);
+			Ok(())
+		}
+
+		// Publish a specification supported by the calling Operator, along with the minimum fee it accepts and the data versions it understands
+		// An already published specification is replaced.
+		#[weight = T::WeightInfo::set_specification(data_versions.len() as u32)]
+		pub fn set_specification(origin, spec_index: SpecIndex, min_fee: BalanceOf<T>, data_versions: Vec<DataVersion>) -> DispatchResult {
+			let who : <T as frame_system::Trait>::AccountId = ensure_signed(origin)?;
+
+			ensure!(<Operators<T>>::contains_key(&who), Error::<T>::UnknownOperator);
+			ensure!(!data_versions.is_empty(), Error::<T>::UnsupportedDataVersion);
+
+			Specifications::<T>::insert(&who, &spec_index, Specification { min_fee, data_versions });
+
+			Self::deposit_event(RawEvent::SpecificationSet(who, spec_index));
+			Ok(())
+		}
+
+		// Withdraw a specification previously published by the calling Operator
+		#[weight = T::WeightInfo::remove_specification()]
+		pub fn remove_specification(origin, spec_index: SpecIndex) -> DispatchResult {
+			let who : <T as frame_system::Trait>::AccountId = ensure_signed(origin)?;
+
+			ensure!(Specifications::<T>::contains_key(&who, &spec_index), Error::<T>::UnknownSpecification);
+
+			Specifications::<T>::remove(&who, &spec_index);
+
+			Self::deposit_event(RawEvent::SpecificationRemoved(who, spec_index));
+			Ok(())
+		}
+
 		// Hint specified Operator (via its `AccountId`) of a request to be performed.
 		// Request details are encapsulated in `data` and identified by `spec_index`.
 		// `data` must be SCALE encoded.
+		// The Operator must have published `spec_index`, support `data_version` and accept `fee`.
 		// If provided fee is sufficient, Operator must send back the request result in `callback` Extrinsic which then will dispatch back to the request originator callback identified by `callback`.
 		// The fee is `reserved` and only actually transferred when the result is provided in the callback.
+		// `RequestDeposit` is reserved too, and given back once the request is answered, killed or cancelled.
+		// Fails with `TooManyPendingRequests` or `PendingRequestsLimitReached` if too many requests are already running.
+		// The result must be provided within `validity_period` blocks (`ValidityPeriod` if not provided, at most `MaxValidityPeriod`), or the request is killed.
 		// Operators are expected to listen to `OracleRequest` events. This event contains all the required information to perform the request and provide back the result.
-		#[weight = 0]
-		pub fn initiate_request(origin, operator: T::AccountId, spec_index: SpecIndex, data_version: DataVersion, data: Vec<u8>, fee: u32, callback: <T as Trait>::Callback) -> DispatchResult {
+		#[weight = T::WeightInfo::initiate_request()]
+		pub fn initiate_request(origin, operator: T::AccountId, spec_index: SpecIndex, data_version: DataVersion, data: Vec<u8>, fee: BalanceOf<T>, validity_period: Option<T::BlockNumber>, callback: <T as Trait>::Callback) -> DispatchResult {
 			let who : <T as frame_system::Trait>::AccountId = ensure_signed(origin.clone())?;
 
-			ensure!(<Operators<T>>::contains_key(operator.clone()), Error::<T>::UnknownOperator);
-			ensure!(fee > 0, Error::<T>::InsufficientFee);
+			Self::ensure_specification_supported(&operator, &spec_index, data_version, fee)?;
+			Self::ensure_callback_supported(&callback)?;
+			let validity_period = Self::checked_validity_period(validity_period)?;
+			Self::ensure_pending_requests_allowed(&who, 1)?;
 
-			T::Currency::reserve(&who, fee.into())?;
+			T::Currency::reserve(&who, fee.saturating_add(T::RequestDeposit::get()))?;
 
-			let request_id = NextRequestIdentifier::get();
-			NextRequestIdentifier::put(request_id + 1);
+			Self::store_request(who, operator, spec_index, data_version, data, fee, validity_period, callback);
+			Ok(())
 
-			let now = frame_system::Module::<T>::block_number();
-			Requests::<T>::insert(request_id.clone(), (operator.clone(), vec![callback], now, fee));
+		}
 
-			Self::deposit_event(RawEvent::OracleRequest(operator, spec_index, request_id, who, data_version, data, "Chainlink.callback".into(), fee));
+		// Fan out a request to several Operators, and dispatch their aggregated result once `threshold` of them answered.
+		// Each Operator receives its own sub request (and `OracleRequest` event), so Operators don't have to be aware of the aggregation.
+		// `fee` is reserved for each Operator and transferred as each of them answers.
+		// Sub requests still running once the aggregated result has been dispatched are dropped and their fee is unreserved.
+		// Sub requests are valid for `validity_period` blocks, as for `initiate_request`.
+		#[weight = T::WeightInfo::initiate_aggregated_request(operators.len() as u32)]
+		pub fn initiate_aggregated_request(origin, operators: Vec<T::AccountId>, spec_index: SpecIndex, data_version: DataVersion, data: Vec<u8>, fee: BalanceOf<T>, validity_period: Option<T::BlockNumber>, mode: AggregationMode, threshold: u32, callback: <T as Trait>::Callback) -> DispatchResult {
+			let who : <T as frame_system::Trait>::AccountId = ensure_signed(origin.clone())?;
+
+			ensure!(threshold > 0 && threshold as usize <= operators.len(), Error::<T>::InvalidThreshold);
+			for (index, operator) in operators.iter().enumerate() {
+				ensure!(!operators[..index].contains(operator), Error::<T>::DuplicateOperator);
+				Self::ensure_specification_supported(operator, &spec_index, data_version, fee)?;
+			}
+			Self::ensure_callback_supported(&callback)?;
+			let validity_period = Self::checked_validity_period(validity_period)?;
+			Self::ensure_pending_requests_allowed(&who, operators.len() as u32)?;
+
+			let total_fee = fee.saturating_add(T::RequestDeposit::get()).saturating_mul((operators.len() as u32).into());
+			T::Currency::reserve(&who, total_fee)?;
+
+			let aggregation_id = Self::new_request_identifier();
+			Self::record_request(aggregation_id, who.clone(), None);
+			let mut pending = Vec::with_capacity(operators.len());
+			for operator in operators {
+				let request_id = Self::store_request(who.clone(), operator, spec_index.clone(), data_version, data.clone(), fee, validity_period, callback.clone());
+				AggregatedRequestOf::insert(request_id, aggregation_id);
+				pending.push(request_id);
+			}
+
+			Aggregations::<T>::insert(aggregation_id, Aggregation { mode, threshold, pending: pending.clone(), answers: vec![] });
+
+			Self::deposit_event(RawEvent::AggregatedOracleRequest(aggregation_id, who, pending, mode, threshold));
 			Ok(())
+		}
+
+		// Cancel a running request and give its fee back.
+		// Only the requester can cancel a request, once `CancellationGracePeriod` blocks passed since it was initiated.
+		// Cancelling an aggregated request cancels all its sub requests still running.
+		#[weight = T::WeightInfo::cancel_request(T::MaxPendingRequestsPerAccount::get())]
+		pub fn cancel_request(origin, request_id: RequestIdentifier) -> DispatchResult {
+			let who : <T as frame_system::Trait>::AccountId = ensure_signed(origin)?;
+
+			ensure!(!AggregatedRequestOf::contains_key(request_id), Error::<T>::SubRequestNotCancellable);
+			let request_ids = match Self::aggregation(request_id) {
+				Some(aggregation) => aggregation.pending,
+				None => vec![request_id],
+			};
+
+			let now = frame_system::Module::<T>::block_number();
+			let mut requests = Vec::with_capacity(request_ids.len());
+			for id in request_ids {
+				let request = Self::request(id).ok_or(Error::<T>::UnknownRequest)?;
+				ensure!(request.requester == who, Error::<T>::NotRequester);
+				ensure!(now >= request.block_number.saturating_add(T::CancellationGracePeriod::get()), Error::<T>::CancellationGracePeriod);
+				requests.push((id, request));
+			}
+
+			for (id, request) in requests {
+				Requests::<T>::remove(id);
+				Self::release_request(id, &request);
+				AggregatedRequestOf::remove(id);
+				T::Currency::unreserve(&request.requester, request.fee);
+				Self::set_request_status(id, RequestStatus::Cancelled(now));
+			}
+			Aggregations::<T>::remove(request_id);
+			Self::set_request_status(request_id, RequestStatus::Cancelled(now));
 
+			Self::deposit_event(RawEvent::RequestCancelled(request_id));
+			Ok(())
 		}
 
 		// The callback used to be notified of all Operators results.
-		// Only the Operator responsible for an identified request can notify back the result.
+		// Only the Operator responsible for an identified request can notify back the result, until the deadline of the request.
 		// Result is then dispatched back to the originator's callback.
-		// The fee reserved during `initiate_request` is transferred as soon as this callback is called.
-        #[weight = 0]
+		// For sub requests of an aggregated request, the result is only dispatched once enough Operators answered.
+		// The fee reserved during `initiate_request` is transferred as soon as this callback is called,
+		// unless dispatching the result fails and `CallbackFailurePolicy` decides otherwise.
+		// The Operator pays for the dispatched callback too, up to `MaxCallbackWeight`.
+		// Storage changes are discarded if it fails, the request being left as it was.
+        #[weight = T::WeightInfo::callback().max(T::WeightInfo::callback_aggregated(T::MaxPendingRequestsPerAccount::get())).saturating_add(T::MaxCallbackWeight::get())]
+        #[transactional]
         fn callback(origin, request_id: RequestIdentifier, result: Vec<u8>) -> DispatchResult {
 
  			let who : <T as frame_system::Trait>::AccountId = ensure_signed(origin.clone())?;
 
-			ensure!(<Requests<T>>::contains_key(request_id.clone()), Error::<T>::UnknownRequest);
-			ensure!(<Requests<T>>::get(request_id.clone()).0 == who, Error::<T>::WrongOperator);
-
-			let (operator, callback, _, fee) = <Requests<T>>::take(request_id.clone());
-
-			T::Currency::repatriate_reserved(&who, &operator, fee.into(), BalanceStatus::Free)?;
+			let request = match <Requests<T>>::get(request_id.clone()) {
+				Some(request) => request,
+				None => return Err(match Self::request_record(request_id).map(|record| record.status) {
+					Some(RequestStatus::Expired(_)) => Error::<T>::DeadlinePassed,
+					_ => Error::<T>::UnknownRequest,
+				}.into()),
+			};
+			ensure!(request.operator == who, Error::<T>::WrongOperator);
+			ensure!(frame_system::Module::<T>::block_number() <= request.deadline, Error::<T>::DeadlinePassed);
+
+			let aggregation_id = AggregatedRequestOf::get(request_id);
+			if let Some(aggregation) = aggregation_id.and_then(|id| Self::aggregation(id)) {
+				ensure!(aggregation.mode == AggregationMode::Quorum || encoding::decode_result::<i128>(&result).is_ok(), Error::<T>::InvalidAggregatedResult);
+			}
 
-			// Dispatch the result to the original callback registered by the caller
-			callback[0].with_result(result.clone()).ok_or(Error::<T>::UnknownCallback)?.dispatch_bypass_filter(frame_system::RawOrigin::Root.into()).ok();
+			let now = frame_system::Module::<T>::block_number();
+			match aggregation_id {
+				Some(aggregation_id) => {
+					<Requests<T>>::remove(request_id.clone());
+					Self::release_request(request_id, &request);
+					AggregatedRequestOf::remove(request_id);
+					T::Currency::repatriate_reserved(&request.requester, &request.operator, request.fee, BalanceStatus::Free)?;
+					Self::record_answer(&who, request.block_number, request.fee);
+
+					Self::aggregate_answer(aggregation_id, request_id, who.clone(), result.clone(), &request.callback)?;
+					Self::set_request_status(request_id, RequestStatus::Answered(now));
+				}
+				None => {
+					let call = request.callback.with_result(result.clone()).ok_or(Error::<T>::UnknownCallback)?;
+					<Requests<T>>::remove(request_id.clone());
+
+					// Dispatch the result to the original callback registered by the caller
+					match Self::dispatch_callback(call) {
+						Ok(_) => Self::set_request_status(request_id, RequestStatus::Answered(now)),
+						Err(error) => {
+							Self::set_request_status(request_id, RequestStatus::CallbackFailed(now));
+							Self::deposit_event(RawEvent::CallbackFailed(request_id, error));
+
+							match T::CallbackFailurePolicy::get() {
+								CallbackFailurePolicy::PayOperator => (),
+								CallbackFailurePolicy::RefundRequester => {
+									Self::release_request(request_id, &request);
+									T::Currency::unreserve(&request.requester, request.fee);
+									Self::record_answer(&who, request.block_number, Zero::zero());
+									return Ok(());
+								}
+								CallbackFailurePolicy::KeepOpen => {
+									// Still indexed in `ExpiringRequests`, it will be killed if no other result is provided in time
+									// The Operator did its job though, it won't be slashed for it
+									<Requests<T>>::insert(request_id, request);
+									AnsweredRequests::insert(request_id, true);
+									return Ok(());
+								}
+							}
+						}
+					}
+
+					Self::release_request(request_id, &request);
+					T::Currency::repatriate_reserved(&request.requester, &request.operator, request.fee, BalanceStatus::Free)?;
+					Self::record_answer(&who, request.block_number, request.fee);
+				}
+			}
 
-			Self::deposit_event(RawEvent::OracleAnswer(operator, request_id, who, result, fee));
+			Self::deposit_event(RawEvent::OracleAnswer(request.operator, request_id, who, result, request.fee));
             Ok(())
 		}
 
-		// Identify requests that are considered dead and remove them
-		fn on_finalize(n: T::BlockNumber) {
-			// Adding a request requires a fee, enumeration is safe
-			for (request_identifier, (_account_id, _data, block_number, _fee)) in Requests::<T>::iter() {
-				if n > block_number + T::ValidityPeriod::get() {
-					// No result has been received in time
-					Requests::<T>::remove(request_identifier);
+		// Unreserve the fee of a request killed by the `Releases::V2` migration
+		// Requests stored before `Releases::V2` didn't record their requester: it must be provided, as found in the `OracleRequest` event.
+		#[weight = T::WeightInfo::refund_legacy_request()]
+		pub fn refund_legacy_request(origin, request_id: RequestIdentifier, requester: T::AccountId) -> DispatchResult {
+			ensure_root(origin)?;
+
+			let fee = LegacyRequestFees::<T>::take(request_id).ok_or(Error::<T>::UnknownRequest)?;
+			T::Currency::unreserve(&requester, fee);
 
-					Self::deposit_event(RawEvent::KillRequest(request_identifier));
+			Self::deposit_event(RawEvent::LegacyRequestRefunded(request_id, requester, fee));
+			Ok(())
+		}
+
+		// Remove requests that didn't receive any result in time
+		// Only the requests expiring in this block are considered, answered ones are skipped
+		fn on_initialize(n: T::BlockNumber) -> Weight {
+			let mut weight = T::DbWeight::get().reads_writes(1, 1);
+			for request_identifier in ExpiringRequests::<T>::take(n) {
+				weight = weight.saturating_add(T::DbWeight::get().reads(1));
+				if let Some(request) = Self::request(request_identifier) {
+					weight = weight.saturating_add(Self::kill_request(request_identifier, request));
 				}
 			}
+			weight
+		}
+
+		fn on_runtime_upgrade() -> Weight {
+			let mut weight = 0;
+			if StorageVersion::get() == Releases::V1 {
+				weight = weight.saturating_add(Self::migrate_to_v2());
+			}
+			if StorageVersion::get() == Releases::V2 {
+				weight = weight.saturating_add(Self::migrate_to_v3());
+			}
+			if StorageVersion::get() == Releases::V3 {
+				weight = weight.saturating_add(Self::migrate_to_v4());
+			}
+			if StorageVersion::get() == Releases::V4 {
+				weight = weight.saturating_add(Self::migrate_to_v5());
+			}
+			if StorageVersion::get() == Releases::V5 {
+				weight = weight.saturating_add(Self::migrate_to_v6());
+			}
+			weight
 		}
 
 	}
 }
 
-#[cfg(test)]
-mod tests {
-	use super::*;
+impl<T: Trait> Module<T> {
 
-	use frame_support::{impl_outer_event, impl_outer_origin, parameter_types, weights::Weight};
-	use sp_core::H256;
-	// The testing primitives are very useful for avoiding having to work with signatures
-	// or public keys. `u64` is used as the `AccountId` and no `Signature`s are required.
-	use sp_runtime::{
-		Perbill,
-		testing::Header,
-		traits::{BlakeTwo256, OnFinalize, IdentityLookup},
-	};
-	use frame_system::{EventRecord, Phase};
+	// The history of a request, if it is still recorded
+	pub fn request_status(request_id: RequestIdentifier) -> Option<RequestRecord<T::AccountId, T::BlockNumber>> {
+		Self::request_record(request_id)
+	}
 
-	impl_outer_origin! {
-		pub enum Origin for Runtime {}
+	// Registered Operators with their performance, best first
+	pub fn leaderboard() -> Vec<(T::AccountId, OperatorStats<BalanceOf<T>, T::BlockNumber>)> {
+		let mut leaderboard: Vec<_> = Operators::<T>::iter()
+			.filter(|(_, registered)| *registered)
+			.map(|(operator, _)| {
+				let stats = Self::operator_stats(&operator);
+				(operator, stats)
+			})
+			.collect();
+		leaderboard.sort_by(|(_, stats), (_, other)| {
+			if stats.ranks_before(other) {
+				sp_std::cmp::Ordering::Less
+			} else if other.ranks_before(stats) {
+				sp_std::cmp::Ordering::Greater
+			} else {
+				sp_std::cmp::Ordering::Equal
+			}
+		});
+		leaderboard
 	}
 
-	#[derive(Clone, Eq, PartialEq, Debug)]
-	pub struct Runtime;
-	parameter_types! {
-		pub const BlockHashCount: u64 = 250;
-		pub const MaximumBlockWeight: Weight = 1024;
-		pub const MaximumBlockLength: u32 = 2 * 1024;
-		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	// The best performing registered Operator supporting a specification, if any
+	pub fn best_operator(spec_index: &SpecIndex) -> Option<T::AccountId> {
+		Self::leaderboard().into_iter()
+			.map(|(operator, _)| operator)
+			.find(|operator| Specifications::<T>::contains_key(operator, spec_index))
 	}
-	impl frame_system::Trait for Runtime {
-		type Origin = Origin;
-		type Index = u64;
-		type BlockNumber = u64;
-		type Hash = H256;
-		type Call = ();
-		type Hashing = BlakeTwo256;
-		type AccountId = u64;
-		type Lookup = IdentityLookup<Self::AccountId>;
-		type Header = Header;
-		type Event = TestEvent;
-		type BlockHashCount = BlockHashCount;
-		type MaximumBlockWeight = MaximumBlockWeight;
-		type MaximumBlockLength = MaximumBlockLength;
-		type AvailableBlockRatio = AvailableBlockRatio;
-		type Version = ();
-		type ModuleToIndex = ();
+
+	// The history of the most recent requests of a requester, oldest first
+	pub fn requests_of(requester: T::AccountId) -> Vec<(RequestIdentifier, RequestRecord<T::AccountId, T::BlockNumber>)> {
+		Self::requester_history(requester).into_iter()
+			.filter_map(|request_id| Self::request_record(request_id).map(|record| (request_id, record)))
+			.collect()
 	}
-	parameter_types! {
-		pub const ExistentialDeposit: u64 = 1;
-		pub const TransferFee: u64 = 0;
-		pub const CreationFee: u64 = 0;
+
+	fn new_request_identifier() -> RequestIdentifier {
+		let request_id = NextRequestIdentifier::get();
+		NextRequestIdentifier::put(request_id + 1);
+		request_id
 	}
-	impl balances::Trait for Runtime {
-		type Balance = u64;
-		type OnFreeBalanceZero = ();
-		type OnNewAccount = ();
-		type DustRemoval = ();
-		type TransferPayment = ();
-		type Event = TestEvent;
-		type ExistentialDeposit = ExistentialDeposit;
-		type TransferFee = TransferFee;
-		type CreationFee = CreationFee;
+
+	// Check that an Operator supports a specification, in this data version and for this fee
+	fn ensure_specification_supported(operator: &T::AccountId, spec_index: &SpecIndex, data_version: DataVersion, fee: BalanceOf<T>) -> DispatchResult {
+		ensure!(<Operators<T>>::contains_key(operator), Error::<T>::UnknownOperator);
+
+		let specification = Self::specification(operator, spec_index).ok_or(Error::<T>::UnknownSpecification)?;
+		ensure!(specification.data_versions.contains(&data_version), Error::<T>::UnsupportedDataVersion);
+		ensure!(!fee.is_zero() && fee >= specification.min_fee, Error::<T>::InsufficientFee);
+		Ok(())
 	}
-	impl Trait for Runtime {
+
+	// Check that a callback can receive a result, and is light enough to be dispatched, as is its failure notification
+	fn ensure_callback_supported(callback: &<T as Trait>::Callback) -> DispatchResult {
+		ensure!(callback.with_result(Vec::new()).is_some(), Error::<T>::UnknownCallback);
+		let weight = callback.on_failure().map_or(0, |failure| failure.get_dispatch_info().weight).max(callback.get_dispatch_info().weight);
+		ensure!(weight <= T::MaxCallbackWeight::get(), Error::<T>::CallbackTooHeavy);
+		Ok(())
+	}
+
+	// Ensure `who` can have `count` more requests running
+	fn ensure_pending_requests_allowed(who: &T::AccountId, count: u32) -> DispatchResult {
+		ensure!(Self::pending_requests(who).saturating_add(count) <= T::MaxPendingRequestsPerAccount::get(), Error::<T>::TooManyPendingRequests);
+		ensure!(Self::pending_request_count().saturating_add(count) <= T::MaxPendingRequests::get(), Error::<T>::PendingRequestsLimitReached);
+		Ok(())
+	}
+
+	// The validity period of a new request, `ValidityPeriod` unless one is provided
+	fn checked_validity_period(validity_period: Option<T::BlockNumber>) -> Result<T::BlockNumber, DispatchError> {
+		let validity_period = validity_period.unwrap_or_else(T::ValidityPeriod::get);
+		ensure!(!validity_period.is_zero() && validity_period <= T::MaxValidityPeriod::get(), Error::<T>::InvalidValidityPeriod);
+		Ok(validity_period)
+	}
+
+	// Store a new request, which fee and deposit have already been reserved, and notify the Operator
+	// The request is valid for `validity_period` blocks after the current one
+	fn store_request(who: T::AccountId, operator: T::AccountId, spec_index: SpecIndex, data_version: DataVersion, data: Vec<u8>, fee: BalanceOf<T>, validity_period: T::BlockNumber, callback: <T as Trait>::Callback) -> RequestIdentifier {
+		let request_id = Self::new_request_identifier();
+
+		let now = frame_system::Module::<T>::block_number();
+		let deadline = now.saturating_add(validity_period);
+		Requests::<T>::insert(request_id.clone(), Request { operator: operator.clone(), requester: who.clone(), callback, block_number: now, fee, deadline });
+		ExpiringRequests::<T>::mutate(Self::expiry_block(deadline), |requests| requests.push(request_id));
+		OperatorRequests::<T>::insert(&operator, request_id, true);
+		RequestDeposits::<T>::insert(request_id, T::RequestDeposit::get());
+		PendingRequests::<T>::mutate(&who, |pending| *pending = pending.saturating_add(1));
+		PendingRequestCount::mutate(|pending| *pending = pending.saturating_add(1));
+		Self::record_request(request_id, who.clone(), Some(operator.clone()));
+		OperatorStatistics::<T>::mutate(&operator, |stats| stats.assigned = stats.assigned.saturating_add(1));
+
+		Self::deposit_event(RawEvent::OracleRequest(operator, spec_index, request_id, who, data_version, data, "Chainlink.callback".into(), fee));
+		request_id
+	}
+
+	// Record the answer of an Operator to a sub request, and dispatch the aggregated result as soon as enough answers are available
+	fn aggregate_answer(aggregation_id: RequestIdentifier, request_id: RequestIdentifier, operator: T::AccountId, result: Vec<u8>, callback: &<T as Trait>::Callback) -> DispatchResult {
+		let mut aggregation = match Self::aggregation(aggregation_id) {
+			Some(aggregation) => aggregation,
+			None => return Ok(()),
+		};
+		aggregation.pending.retain(|id| *id != request_id);
+		aggregation.answers.push((operator, result));
+
+		if let Some(aggregated_result) = aggregation.aggregated_result() {
+			Aggregations::<T>::remove(aggregation_id);
+
+			// Remaining sub requests are not needed anymore
+			let now = frame_system::Module::<T>::block_number();
+			for pending_request in aggregation.pending {
+				AggregatedRequestOf::remove(pending_request);
+				if let Some(request) = <Requests<T>>::take(pending_request) {
+					Self::release_request(pending_request, &request);
+					T::Currency::unreserve(&request.requester, request.fee);
+				}
+				Self::set_request_status(pending_request, RequestStatus::Dropped(now));
+			}
+
+			// Operators already got paid for their answers, whatever the `CallbackFailurePolicy`
+			match Self::dispatch_callback(callback.with_result(aggregated_result.clone()).ok_or(Error::<T>::UnknownCallback)?) {
+				Ok(_) => Self::set_request_status(aggregation_id, RequestStatus::Answered(now)),
+				Err(error) => {
+					Self::set_request_status(aggregation_id, RequestStatus::CallbackFailed(now));
+					Self::deposit_event(RawEvent::CallbackFailed(aggregation_id, error));
+				}
+			}
+
+			Self::deposit_event(RawEvent::AggregatedOracleAnswer(aggregation_id, aggregated_result));
+		} else if aggregation.pending.is_empty() {
+			Aggregations::<T>::remove(aggregation_id);
+			Self::set_request_status(aggregation_id, RequestStatus::AggregationFailed(frame_system::Module::<T>::block_number()));
+			Self::deposit_event(RawEvent::AggregationFailed(aggregation_id));
+			Self::notify_failure(callback);
+		} else {
+			Aggregations::<T>::insert(aggregation_id, aggregation);
+		}
+		Ok(())
+	}
+
+	// The block at the beginning of which a request with the given `deadline` is killed
+	fn expiry_block(deadline: T::BlockNumber) -> T::BlockNumber {
+		deadline.saturating_add(One::one())
+	}
+
+	// Remove a request that didn't receive any result in time, and give its fee back to the requester
+	// The Operator is slashed for missing it, unless it answered but the callback failed (see `CallbackFailurePolicy::KeepOpen`)
+	// Returns the weight consumed
+	fn kill_request(request_id: RequestIdentifier, request: Request<T::AccountId, T::Callback, T::BlockNumber, BalanceOf<T>>) -> Weight {
+		let answered = AnsweredRequests::take(request_id);
+		Requests::<T>::remove(request_id);
+		Self::release_request(request_id, &request);
+		T::Currency::unreserve(&request.requester, request.fee);
+
+		// Already marked as `CallbackFailed` otherwise
+		if !answered {
+			Self::slash_bond(&request.operator, T::MissedRequestSlash::get() * Self::operator_bond(&request.operator));
+			Self::set_request_status(request_id, RequestStatus::Expired(frame_system::Module::<T>::block_number()));
+			OperatorStatistics::<T>::mutate(&request.operator, |stats| stats.expired = stats.expired.saturating_add(1));
+		}
+
+		Self::deposit_event(RawEvent::KillRequest(request_id));
+
+		let weight = T::DbWeight::get().reads_writes(11, 12);
+		match AggregatedRequestOf::take(request_id) {
+			Some(aggregation_id) => weight.saturating_add(Self::abandon_aggregated_request(aggregation_id, request_id, &request.callback)),
+			None => weight.saturating_add(Self::notify_failure(&request.callback)),
+		}
+	}
+
+	// Cancel a request whose Operator has been removed, and give its fee back to the requester
+	fn cancel_operator_request(request_id: RequestIdentifier, request: Request<T::AccountId, T::Callback, T::BlockNumber, BalanceOf<T>>) {
+		Requests::<T>::remove(request_id);
+		Self::release_request(request_id, &request);
+		T::Currency::unreserve(&request.requester, request.fee);
+		Self::set_request_status(request_id, RequestStatus::Cancelled(frame_system::Module::<T>::block_number()));
+
+		Self::deposit_event(RawEvent::RequestCancelled(request_id));
+
+		match AggregatedRequestOf::take(request_id) {
+			Some(aggregation_id) => Self::abandon_aggregated_request(aggregation_id, request_id, &request.callback),
+			None => Self::notify_failure(&request.callback),
+		};
+	}
+
+	// Give its deposit back to the requester of a request that is not running anymore
+	fn release_request(request_id: RequestIdentifier, request: &Request<T::AccountId, T::Callback, T::BlockNumber, BalanceOf<T>>) {
+		AnsweredRequests::remove(request_id);
+		OperatorRequests::<T>::remove(&request.operator, request_id);
+		T::Currency::unreserve(&request.requester, RequestDeposits::<T>::take(request_id));
+		PendingRequests::<T>::mutate(&request.requester, |pending| *pending = pending.saturating_sub(1));
+		PendingRequestCount::mutate(|pending| *pending = pending.saturating_sub(1));
+	}
+
+	// Forget about a sub request that will never be answered
+	fn abandon_aggregated_request(aggregation_id: RequestIdentifier, request_id: RequestIdentifier, callback: &<T as Trait>::Callback) -> Weight {
+		if let Some(mut aggregation) = Self::aggregation(aggregation_id) {
+			aggregation.pending.retain(|id| *id != request_id);
+			if aggregation.pending.is_empty() {
+				Aggregations::<T>::remove(aggregation_id);
+				Self::set_request_status(aggregation_id, RequestStatus::AggregationFailed(frame_system::Module::<T>::block_number()));
+				Self::deposit_event(RawEvent::AggregationFailed(aggregation_id));
+				return Self::notify_failure(callback);
+			} else {
+				Aggregations::<T>::insert(aggregation_id, aggregation);
+			}
+		}
+		0
+	}
+
+	// Update the statistics of an Operator that answered a request initiated at `block_number`
+	fn record_answer(operator: &T::AccountId, block_number: T::BlockNumber, earned: BalanceOf<T>) {
+		let latency = frame_system::Module::<T>::block_number().saturating_sub(block_number);
+		OperatorStatistics::<T>::mutate(operator, |stats| {
+			stats.answered = stats.answered.saturating_add(1);
+			stats.total_latency = stats.total_latency.saturating_add(latency);
+			stats.earned = stats.earned.saturating_add(earned);
+		});
+	}
+
+	// Start the history of a new request, pruning the oldest record of the requester if `HistoryDepth` is reached
+	fn record_request(request_id: RequestIdentifier, requester: T::AccountId, operator: Option<T::AccountId>) {
+		let block_number = frame_system::Module::<T>::block_number();
+		RequesterHistory::<T>::mutate(&requester, |history| {
+			history.push(request_id);
+			let excess = history.len().saturating_sub(T::HistoryDepth::get() as usize);
+			for pruned in history.drain(..excess) {
+				RequestHistory::<T>::remove(pruned);
+			}
+		});
+		RequestHistory::<T>::insert(request_id, RequestRecord { requester, operator, block_number, status: RequestStatus::Pending });
+	}
+
+	// Update the status of a request, if it is still recorded
+	fn set_request_status(request_id: RequestIdentifier, status: RequestStatus<T::BlockNumber>) {
+		RequestHistory::<T>::mutate(request_id, |record| {
+			if let Some(record) = record {
+				record.status = status;
+			}
+		});
+	}
+
+	// Slash up to `amount` from the bond of an Operator
+	// Returns the amount actually slashed
+	fn slash_bond(operator: &T::AccountId, amount: BalanceOf<T>) -> BalanceOf<T> {
+		let bond = Self::operator_bond(operator);
+		let (imbalance, _) = T::Currency::slash_reserved(operator, amount.min(bond));
+		let slashed = imbalance.peek();
+		T::Slash::on_unbalanced(imbalance);
+
+		if !slashed.is_zero() {
+			OperatorBonds::<T>::insert(operator, bond.saturating_sub(slashed));
+			Self::deposit_event(RawEvent::OperatorSlashed(operator.clone(), slashed));
+		}
+		slashed
+	}
+
+	// Let the originator know its request failed, if it asked to
+	// Returns the weight of the dispatched Call
+	fn notify_failure(callback: &<T as Trait>::Callback) -> Weight {
+		match callback.on_failure() {
+			Some(failure) => {
+				let weight = failure.get_dispatch_info().weight.min(T::MaxCallbackWeight::get());
+				Self::dispatch_callback(failure).ok();
+				weight
+			}
+			None => 0,
+		}
+	}
+
+	// Dispatch a result (or a failure) to a callback, unless it is heavier than `MaxCallbackWeight`
+	fn dispatch_callback(call: <T as Trait>::Callback) -> Result<(), DispatchError> {
+		ensure!(call.get_dispatch_info().weight <= T::MaxCallbackWeight::get(), Error::<T>::CallbackTooHeavy);
+		call.dispatch_bypass_filter(frame_system::RawOrigin::Root.into()).map(|_| ()).map_err(|error| error.error)
+	}
+
+	// Requests stored before the requester was recorded can't be answered anymore: they are killed
+	// Their fees stay reserved until `refund_legacy_request` is called with their requester
+	// Their callbacks may not decode as `T::Callback` anymore, so only the `u32` fee ending their raw value is read.
+	fn migrate_to_v2() -> Weight {
+		let mut migrated: Weight = 0;
+		for (key, RawValue(value)) in StorageIterator::<RawValue>::new(b"Chainlink", b"Requests").drain() {
+			// Keys are hashed with `Twox64Concat`: the identifier follows its 8 bytes hash
+			let request_id = key.get(8..).and_then(|mut id| RequestIdentifier::decode(&mut id).ok());
+			let fee = value.len().checked_sub(4).and_then(|start| u32::decode(&mut &value[start..]).ok());
+			if let (Some(request_id), Some(fee)) = (request_id, fee) {
+				LegacyRequestFees::<T>::insert(request_id, BalanceOf::<T>::from(fee));
+				Self::deposit_event(RawEvent::KillRequest(request_id));
+			}
+			migrated += 1;
+		}
+		StorageVersion::put(Releases::V2);
+		T::DbWeight::get().reads_writes(migrated.saturating_add(1), migrated.saturating_mul(2).saturating_add(1))
+	}
+
+	// Index running requests by expiry block
+	// Requests that should have already expired are killed in the next block
+	fn migrate_to_v3() -> Weight {
+		let next_block = frame_system::Module::<T>::block_number().saturating_add(One::one());
+		let mut migrated: Weight = 0;
+		for (key, request) in StorageIterator::<OldRequest<T::AccountId, T::Callback, T::BlockNumber, u32>>::new(b"Chainlink", b"Requests") {
+			// Keys are hashed with `Twox64Concat`: the identifier follows its 8 bytes hash
+			if let Ok(request_identifier) = RequestIdentifier::decode(&mut &key[8..]) {
+				let expiry = Self::expiry_block(request.block_number.saturating_add(T::ValidityPeriod::get())).max(next_block);
+				ExpiringRequests::<T>::mutate(expiry, |requests| requests.push(request_identifier));
+			}
+			migrated += 1;
+		}
+		StorageVersion::put(Releases::V3);
+		T::DbWeight::get().reads_writes(migrated.saturating_add(2), migrated.saturating_add(1))
+	}
+
+	// Convert `u32` fees of running requests and published specifications to `BalanceOf<T>`
+	fn migrate_to_v4() -> Weight {
+		for (key, request) in StorageIterator::<OldRequest<T::AccountId, T::Callback, T::BlockNumber, u32>>::new(b"Chainlink", b"Requests").drain() {
+			put_storage_value(b"Chainlink", b"Requests", &key, OldRequest::<_, _, _, BalanceOf<T>> {
+				operator: request.operator,
+				requester: request.requester,
+				callback: request.callback,
+				block_number: request.block_number,
+				fee: request.fee.into(),
+			});
+		}
+		Specifications::<T>::translate::<Specification<u32>, _>(|_, _, specification| {
+			Some(Specification { min_fee: specification.min_fee.into(), data_versions: specification.data_versions })
+		});
+		StorageVersion::put(Releases::V4);
+		T::MaximumBlockWeight::get()
+	}
+
+	// Give running requests the deadline they used to have, `ValidityPeriod` blocks after their initiation
+	fn migrate_to_v5() -> Weight {
+		Requests::<T>::translate::<OldRequest<T::AccountId, T::Callback, T::BlockNumber, BalanceOf<T>>, _>(|_, request| {
+			Some(Request {
+				operator: request.operator,
+				requester: request.requester,
+				callback: request.callback,
+				block_number: request.block_number,
+				fee: request.fee,
+				deadline: request.block_number.saturating_add(T::ValidityPeriod::get()),
+			})
+		});
+		StorageVersion::put(Releases::V5);
+		T::MaximumBlockWeight::get()
+	}
+
+	// Count running requests, so that they are considered by the pending requests limits
+	// They were initiated without deposit, none will be given back
+	fn migrate_to_v6() -> Weight {
+		let mut migrated: Weight = 0;
+		for (_, request) in Requests::<T>::iter() {
+			PendingRequests::<T>::mutate(&request.requester, |pending| *pending = pending.saturating_add(1));
+			migrated += 1;
+		}
+		PendingRequestCount::put(migrated as u32);
+		StorageVersion::put(Releases::V6);
+		T::DbWeight::get().reads_writes(migrated.saturating_mul(2).saturating_add(1), migrated.saturating_add(2))
+	}
+
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use frame_support::{impl_outer_dispatch, impl_outer_event, impl_outer_origin, parameter_types, weights::{RuntimeDbWeight, Weight}};
+	use frame_support::{Blake2_128Concat, StorageHasher, Twox64Concat};
+	use frame_support::storage::migration::put_storage_value;
+	use frame_support::traits::{OnInitialize, OnRuntimeUpgrade};
+	use sp_core::H256;
+	// The testing primitives are very useful for avoiding having to work with signatures
+	// or public keys. `u64` is used as the `AccountId` and no `Signature`s are required.
+	use sp_runtime::{
+		Perbill,
+		testing::Header,
+		traits::{BlakeTwo256, IdentityLookup},
+	};
+	use frame_system::{EventRecord, Phase};
+	use std::cell::RefCell;
+
+	impl_outer_origin! {
+		pub enum Origin for Runtime {}
+	}
+
+	#[derive(Clone, Eq, PartialEq, Debug)]
+	pub struct Runtime;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+		pub const DbWeight: RuntimeDbWeight = RuntimeDbWeight { read: 1, write: 10 };
+	}
+	impl frame_system::Trait for Runtime {
+		type BaseCallFilter = ();
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = ();
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = TestEvent;
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type DbWeight = DbWeight;
+		type BlockExecutionWeight = ();
+		type ExtrinsicBaseWeight = ();
+		type MaximumExtrinsicWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+		type AccountData = balances::AccountData<u64>;
+		type OnNewAccount = ();
+		type OnKilledAccount = ();
+		type SystemWeightInfo = ();
+	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 1;
+	}
+	impl balances::Trait for Runtime {
+		type Balance = u64;
+		type Event = TestEvent;
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type AccountStore = frame_system::Module<Runtime>;
+		type WeightInfo = ();
+	}
+	impl Trait for Runtime {
 		type Event = TestEvent;
 		type Currency = balances::Module<Runtime>;
-		type Callback = module2::Call<Runtime>;
+		type Callback = Call;
 		type ValidityPeriod = ValidityPeriod;
+		type MaxValidityPeriod = MaxValidityPeriod;
+		type OperatorBond = OperatorBond;
+		type UnbondingPeriod = UnbondingPeriod;
+		type MissedRequestSlash = MissedRequestSlash;
+		type SlashOrigin = frame_system::EnsureRoot<u64>;
+		type Slash = ();
+		type HistoryDepth = HistoryDepth;
+		type CallbackFailurePolicy = TestCallbackFailurePolicy;
+		type CancellationGracePeriod = CancellationGracePeriod;
+		type RegistrationOrigin = frame_system::EnsureRoot<u64>;
+		type MaxPendingRequestsPerAccount = MaxPendingRequestsPerAccount;
+		type MaxPendingRequests = MaxPendingRequests;
+		type RequestDeposit = TestRequestDeposit;
+		type MaxCallbackWeight = MaxCallbackWeight;
+		type WeightInfo = ();
 	}
 	impl module2::Trait for Runtime {
 	}
+	impl module3::Trait for Runtime {
+	}
 	parameter_types! {
 		pub const ValidityPeriod: u64 = 10;
+		pub const MaxValidityPeriod: u64 = 20;
+		pub const OperatorBond: u64 = 5;
+		pub const UnbondingPeriod: u64 = 5;
+		pub const MissedRequestSlash: Perbill = Perbill::from_percent(20);
+		pub const HistoryDepth: u32 = 4;
+		pub const CancellationGracePeriod: u64 = 3;
+		pub const MaxPendingRequestsPerAccount: u32 = 5;
+		pub const MaxPendingRequests: u32 = 8;
+		pub const MaxCallbackWeight: Weight = 100;
+	}
+
+	thread_local! {
+		static CALLBACK_FAILURE_POLICY: RefCell<CallbackFailurePolicy> = RefCell::new(CallbackFailurePolicy::PayOperator);
+		static REQUEST_DEPOSIT: RefCell<u64> = RefCell::new(0);
+	}
+	pub struct TestCallbackFailurePolicy;
+	impl Get<CallbackFailurePolicy> for TestCallbackFailurePolicy {
+		fn get() -> CallbackFailurePolicy {
+			CALLBACK_FAILURE_POLICY.with(|policy| *policy.borrow())
+		}
+	}
+	pub struct TestRequestDeposit;
+	impl Get<u64> for TestRequestDeposit {
+		fn get() -> u64 {
+			REQUEST_DEPOSIT.with(|deposit| *deposit.borrow())
+		}
 	}
 
 	mod chainlink {
@@ -273,21 +1481,41 @@ mod tests {
 
 	impl_outer_event! {
 		pub enum TestEvent for Runtime {
+			frame_system<T>,
 			balances<T>,
 			chainlink<T>,
 		}
 	}
 
+	impl_outer_dispatch! {
+		pub enum Call for Runtime where origin: Origin {
+			module2::Module2,
+			module3::Module3,
+		}
+	}
+
+	crate::impl_callback_with_parameter!(Call { Module2, Module3 });
+
+	#[cfg(feature = "runtime-benchmarks")]
+	impl crate::benchmarking::Trait for Runtime {
+		fn callback() -> Call {
+			module2::Call::<Runtime>::callback(vec![]).into()
+		}
+	}
+
 	type System = frame_system::Module<Runtime>;
+	type Balances = balances::Module<Runtime>;
+	type Module2 = module2::Module<Runtime>;
+	type Module3 = module3::Module<Runtime>;
 
-	fn new_test_ext() -> sp_io::TestExternalities {
+	pub(crate) fn new_test_ext() -> sp_io::TestExternalities {
 		let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
 		balances::GenesisConfig::<Runtime>{
-			balances: vec![(1, 10), (2, 20)],
-			vesting: vec![],
+			balances: vec![(1, 10), (2, 20), (3, 30), (4, 40)],
 		}.assimilate_storage(&mut t).unwrap();
-		balances::GenesisConfig::<Runtime>::default().assimilate_storage(&mut t).unwrap();
-		t.into()
+		let mut ext: sp_io::TestExternalities = t.into();
+		ext.execute_with(|| System::set_block_number(1));
+		ext
 	}
 
 	mod module2 {
@@ -299,17 +1527,25 @@ mod tests {
 			pub struct Module<T: Trait> for enum Call
 				where origin: <T as frame_system::Trait>::Origin
 			{
+				#[weight = 0]
 				pub fn callback(_origin, result: Vec<u8>) -> frame_support::dispatch::DispatchResult {
 					let r : u128 = u128::decode(&mut &result[..]).map_err(|err| err.what())?;
 					<Result>::put(r);
 					Ok(())
 				}
+
+				#[weight = 0]
+				pub fn failure(_origin) -> frame_support::dispatch::DispatchResult {
+					<Failures>::mutate(|failures| *failures += 1);
+					Ok(())
+				}
 			}
 		}
 
 		frame_support::decl_storage! {
 			trait Store for Module<T: Trait> as TestStorage {
 				pub Result: u128;
+				pub Failures: u32;
 			}
 		}
 
@@ -320,23 +1556,260 @@ mod tests {
 					_ => None
 				}
 			}
+
+			fn on_failure(&self) -> Option<Self> {
+				Some(Call::failure())
+			}
+		}
+
+	}
+
+	// Another consumer, storing results as they are
+	mod module3 {
+		use super::*;
+
+		pub trait Trait: frame_system::Trait {}
+
+		frame_support::decl_module! {
+			pub struct Module<T: Trait> for enum Call
+				where origin: <T as frame_system::Trait>::Origin
+			{
+				#[weight = result.len() as Weight]
+				pub fn store(_origin, result: Vec<u8>) -> frame_support::dispatch::DispatchResult {
+					<Result>::put(result);
+					Ok(())
+				}
+			}
+		}
+
+		frame_support::decl_storage! {
+			trait Store for Module<T: Trait> as TestStorage3 {
+				pub Result: Vec<u8>;
+			}
+		}
+
+		impl <T: Trait> CallbackWithParameter for Call<T> {
+			fn with_result(&self, result: Vec<u8>) -> Option<Self> {
+				match *self {
+					Call::store(_) => Some(Call::store(result)),
+					_ => None
+				}
+			}
 		}
 
 	}
 
+	fn publish_specification(operator: u64) {
+		assert!(<Module<Runtime>>::set_specification(Origin::signed(operator), vec![], 1, vec![1]).is_ok());
+	}
+
 	#[test]
 	fn operators_can_be_registered() {
 		new_test_ext().execute_with(|| {
-			assert!(!<Operators<Runtime>>::exists(1));
+			assert!(!<Operators<Runtime>>::contains_key(1));
 			assert!(<Module<Runtime>>::register_operator(Origin::signed(1)).is_ok());
-			assert!(<Operators<Runtime>>::exists(1));
+			assert!(<Operators<Runtime>>::contains_key(1));
 			assert!(<Module<Runtime>>::unregister_operator(Origin::signed(1)).is_ok());
-			assert!(!<Operators<Runtime>>::exists(1));
+			assert!(!<Operators<Runtime>>::contains_key(1));
 		});
 
 		new_test_ext().execute_with(|| {
 			assert!(<Module<Runtime>>::unregister_operator(Origin::signed(1)).is_err());
-			assert!(!<Operators<Runtime>>::exists(1));
+			assert!(!<Operators<Runtime>>::contains_key(1));
+		});
+
+	}
+
+	#[test]
+	fn operators_can_be_registered_in_genesis() {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+		balances::GenesisConfig::<Runtime>{
+			balances: vec![(1, 10)],
+		}.assimilate_storage(&mut t).unwrap();
+		GenesisConfig::<Runtime> {
+			operators: vec![(1, vec![(b"job".to_vec(), 2, vec![0])])],
+			..Default::default()
+		}.assimilate_storage(&mut t).unwrap();
+		sp_io::TestExternalities::from(t).execute_with(|| {
+			assert!(<Module<Runtime>>::operator(1));
+			assert_eq!(<Module<Runtime>>::operator_bond(1), 5);
+			assert_eq!(Balances::reserved_balance(1), 5);
+			assert_eq!(<Module<Runtime>>::specification(1, b"job".to_vec()), Some(Specification { min_fee: 2, data_versions: vec![0] }));
+		});
+	}
+
+	#[test]
+	fn operators_are_bonded() {
+
+		new_test_ext().execute_with(|| {
+			assert!(<Module<Runtime>>::register_operator(Origin::signed(1)).is_ok());
+			assert_eq!(Balances::reserved_balance(1), 5);
+			assert_eq!(<Module<Runtime>>::operator_bond(1), 5);
+
+			assert!(<Module<Runtime>>::unregister_operator(Origin::signed(1)).is_ok());
+			assert_eq!(<Module<Runtime>>::unbonding(1), Some(6));
+			assert_eq!(Balances::reserved_balance(1), 5);
+
+			assert_eq!(<Module<Runtime>>::withdraw_unbonded(Origin::signed(1)), Err(Error::<Runtime>::UnbondingInProgress.into()));
+			System::set_block_number(6);
+			assert!(<Module<Runtime>>::withdraw_unbonded(Origin::signed(1)).is_ok());
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert_eq!(Balances::free_balance(1), 10);
+			assert_eq!(
+				System::events().last().unwrap().event,
+				TestEvent::chainlink(RawEvent::BondWithdrawn(1, 5))
+			);
+
+			assert_eq!(<Module<Runtime>>::withdraw_unbonded(Origin::signed(1)), Err(Error::<Runtime>::NotUnbonding.into()));
+		});
+
+		new_test_ext().execute_with(|| {
+			// Not enough funds to bond
+			assert!(<Balances as Currency<u64>>::transfer(&1, &2, 6, frame_support::traits::ExistenceRequirement::AllowDeath).is_ok());
+			assert!(<Module<Runtime>>::register_operator(Origin::signed(1)).is_err());
+			assert!(!<Operators<Runtime>>::contains_key(1));
+		});
+
+	}
+
+	#[test]
+	fn operators_can_be_slashed() {
+
+		new_test_ext().execute_with(|| {
+			assert!(<Module<Runtime>>::register_operator(Origin::signed(3)).is_ok());
+
+			assert!(<Module<Runtime>>::slash_operator(Origin::signed(2), 3, 2).is_err());
+			assert_eq!(<Module<Runtime>>::slash_operator(Origin::root(), 4, 2), Err(Error::<Runtime>::UnknownOperator.into()));
+
+			assert!(<Module<Runtime>>::slash_operator(Origin::root(), 3, 2).is_ok());
+			assert_eq!(<Module<Runtime>>::operator_bond(3), 3);
+			assert_eq!(Balances::reserved_balance(3), 3);
+			assert_eq!(Balances::total_balance(&3), 28);
+			assert_eq!(
+				System::events().last().unwrap().event,
+				TestEvent::chainlink(RawEvent::OperatorSlashed(3, 2))
+			);
+
+			// Can't slash more than the bond, even while unbonding
+			assert!(<Module<Runtime>>::unregister_operator(Origin::signed(3)).is_ok());
+			assert!(<Module<Runtime>>::slash_operator(Origin::root(), 3, 100).is_ok());
+			assert_eq!(<Module<Runtime>>::operator_bond(3), 0);
+			assert_eq!(Balances::free_balance(3), 25);
+
+			// Registering again requires a full bond
+			assert!(<Module<Runtime>>::register_operator(Origin::signed(3)).is_ok());
+			assert_eq!(<Module<Runtime>>::operator_bond(3), 5);
+			assert_eq!(Balances::free_balance(3), 20);
+			assert_eq!(<Module<Runtime>>::unbonding(3), None);
+		});
+
+	}
+
+	#[test]
+	fn operators_can_be_whitelisted() {
+
+		new_test_ext().execute_with(|| {
+			assert!(<Module<Runtime>>::set_whitelist_mode(Origin::signed(1), true).is_err());
+			assert!(<Module<Runtime>>::set_whitelist_mode(Origin::root(), true).is_ok());
+			assert_eq!(
+				System::events().last().unwrap().event,
+				TestEvent::chainlink(RawEvent::WhitelistModeSet(true))
+			);
+
+			assert_eq!(<Module<Runtime>>::register_operator(Origin::signed(1)), Err(Error::<Runtime>::NotApproved.into()));
+			assert!(<Module<Runtime>>::approve_operator(Origin::signed(1), 1).is_err());
+			assert!(<Module<Runtime>>::approve_operator(Origin::root(), 1).is_ok());
+			assert!(<Module<Runtime>>::register_operator(Origin::signed(1)).is_ok());
+
+			// Registration is open again
+			assert!(<Module<Runtime>>::set_whitelist_mode(Origin::root(), false).is_ok());
+			assert!(<Module<Runtime>>::register_operator(Origin::signed(2)).is_ok());
+		});
+
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+		GenesisConfig::<Runtime> {
+			whitelist_mode: true,
+			approved_operators: vec![3],
+			..Default::default()
+		}.assimilate_storage(&mut t).unwrap();
+		balances::GenesisConfig::<Runtime>{
+			balances: vec![(3, 30), (4, 40)],
+		}.assimilate_storage(&mut t).unwrap();
+		sp_io::TestExternalities::from(t).execute_with(|| {
+			assert!(<Module<Runtime>>::whitelist_mode());
+			assert!(<Module<Runtime>>::register_operator(Origin::signed(3)).is_ok());
+			assert_eq!(<Module<Runtime>>::register_operator(Origin::signed(4)), Err(Error::<Runtime>::NotApproved.into()));
+		});
+
+	}
+
+	#[test]
+	fn operators_can_be_removed() {
+
+		new_test_ext().execute_with(|| {
+			register_operators(&[1, 2]);
+			assert!(<Module<Runtime>>::approve_operator(Origin::root(), 1).is_ok());
+			assert!(<Module<Runtime>>::initiate_request(Origin::signed(3), 1, vec![], 1, vec![], 2, None, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+			assert!(<Module<Runtime>>::initiate_request(Origin::signed(3), 2, vec![], 1, vec![], 2, None, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+			assert!(<Module<Runtime>>::initiate_aggregated_request(Origin::signed(4), vec![1, 2], vec![], 1, vec![], 2, None, AggregationMode::Median, 1, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+			assert_eq!(Balances::reserved_balance(3), 4);
+			assert_eq!(Balances::reserved_balance(4), 4);
+
+			assert!(<Module<Runtime>>::remove_operator(Origin::signed(1), 1, 2).is_err());
+			// Approval of unregistered operators is kept
+			assert!(<Module<Runtime>>::approve_operator(Origin::root(), 3).is_ok());
+			assert_eq!(<Module<Runtime>>::remove_operator(Origin::root(), 3, 0), Err(Error::<Runtime>::UnknownOperator.into()));
+			assert!(<Module<Runtime>>::approved_operator(3));
+			// 1 has two pending requests, 0 and 3
+			assert_eq!(<Module<Runtime>>::remove_operator(Origin::root(), 1, 1), Err(Error::<Runtime>::InvalidPendingWitness.into()));
+			assert!(<Module<Runtime>>::operator(1));
+			assert!(<Module<Runtime>>::remove_operator(Origin::root(), 1, 2).is_ok());
+			assert_eq!(
+				System::events().last().unwrap().event,
+				TestEvent::chainlink(RawEvent::OperatorRemoved(1))
+			);
+			assert!(!<Operators<Runtime>>::contains_key(1));
+			assert!(!<Module<Runtime>>::approved_operator(1));
+			assert_eq!(<Module<Runtime>>::unbonding(1), Some(6));
+
+			// Requests of 1 are refunded, others keep running
+			assert!(<Module<Runtime>>::request(0).is_none());
+			assert_eq!(<Module<Runtime>>::request_status(0).unwrap().status, RequestStatus::Cancelled(1));
+			assert_eq!(Balances::reserved_balance(3), 2);
+			assert!(<Module<Runtime>>::request(1).is_some());
+			assert!(<Module<Runtime>>::request(3).is_none());
+			assert_eq!(Balances::reserved_balance(4), 2);
+			assert_eq!(<Module<Runtime>>::aggregation(2).unwrap().pending, vec![4]);
+			assert!(!<Module<Runtime>>::operator_request(1, 0));
+			assert!(<Module<Runtime>>::operator_request(2, 1));
+
+			// The remaining operator can still complete the aggregated request
+			assert!(<Module<Runtime>>::callback(Origin::signed(2), 4, 10i128.encode()).is_ok());
+			assert_eq!(module2::Result::get(), 10);
+		});
+
+	}
+
+	#[test]
+	fn specifications_are_enforced() {
+
+		new_test_ext().execute_with(|| {
+			let callback: Call = module2::Call::<Runtime>::callback(vec![]).into();
+			assert_eq!(<Module<Runtime>>::set_specification(Origin::signed(1), b"job".to_vec(), 3, vec![1, 2]), Err(Error::<Runtime>::UnknownOperator.into()));
+
+			assert!(<Module<Runtime>>::register_operator(Origin::signed(1)).is_ok());
+			assert_eq!(<Module<Runtime>>::set_specification(Origin::signed(1), b"job".to_vec(), 3, vec![]), Err(Error::<Runtime>::UnsupportedDataVersion.into()));
+			assert!(<Module<Runtime>>::set_specification(Origin::signed(1), b"job".to_vec(), 3, vec![1, 2]).is_ok());
+			assert_eq!(<Module<Runtime>>::specification(1, b"job".to_vec()), Some(Specification { min_fee: 3, data_versions: vec![1, 2] }));
+
+			assert_eq!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, b"other".to_vec(), 1, vec![], 3, None, callback.clone()), Err(Error::<Runtime>::UnknownSpecification.into()));
+			assert_eq!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, b"job".to_vec(), 3, vec![], 3, None, callback.clone()), Err(Error::<Runtime>::UnsupportedDataVersion.into()));
+			assert_eq!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, b"job".to_vec(), 2, vec![], 2, None, callback.clone()), Err(Error::<Runtime>::InsufficientFee.into()));
+			assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, b"job".to_vec(), 2, vec![], 3, None, callback.clone()).is_ok());
+
+			assert!(<Module<Runtime>>::remove_specification(Origin::signed(1), b"job".to_vec()).is_ok());
+			assert_eq!(<Module<Runtime>>::remove_specification(Origin::signed(1), b"job".to_vec()), Err(Error::<Runtime>::UnknownSpecification.into()));
+			assert_eq!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, b"job".to_vec(), 2, vec![], 3, None, callback), Err(Error::<Runtime>::UnknownSpecification.into()));
 		});
 
 	}
@@ -346,16 +1819,18 @@ mod tests {
 
 		new_test_ext().execute_with(|| {
 			assert!(<Module<Runtime>>::register_operator(Origin::signed(1)).is_ok());
-			assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 0, module2::Call::<Runtime>::callback(vec![]).into()).is_err());
+			publish_specification(1);
+			assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 0, None, module2::Call::<Runtime>::callback(vec![]).into()).is_err());
 		});
 
 		new_test_ext().execute_with(|| {
-			assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 1, module2::Call::<Runtime>::callback(vec![]).into()).is_err());
+			assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 1, None, module2::Call::<Runtime>::callback(vec![]).into()).is_err());
 		});
 
 		new_test_ext().execute_with(|| {
 			assert!(<Module<Runtime>>::register_operator(Origin::signed(1)).is_ok());
-			assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+			publish_specification(1);
+			assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, None, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
 			assert!(<Module<Runtime>>::callback(Origin::signed(3), 0, 10.encode()).is_err());
 		});
 
@@ -369,20 +1844,21 @@ mod tests {
 			assert_eq!(
 				*System::events().last().unwrap(),
 				EventRecord {
-					phase: Phase::ApplyExtrinsic(0),
+					phase: Phase::Initialization,
 					event: TestEvent::chainlink(RawEvent::OperatorRegistered(1)),
 					topics: vec![],
 				}
 			);
+			publish_specification(1);
 
 			let parameters = ("a", "b");
 			let data = parameters.encode();
-			assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, data.clone(), 2, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+			assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, data.clone(), 2, None, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
 
 			assert_eq!(
 				*System::events().last().unwrap(),
 				EventRecord {
-					phase: Phase::ApplyExtrinsic(0),
+					phase: Phase::Initialization,
 					event: TestEvent::chainlink(RawEvent::OracleRequest(1, vec![], 0, 2, 1, data.clone(), "Chainlink.callback".into(), 2)),
 					topics: vec![],
 				}
@@ -399,16 +1875,643 @@ mod tests {
 	}
 
 	#[test]
-	pub fn on_finalize() {
+	pub fn on_initialize() {
 
 		new_test_ext().execute_with(|| {
 			assert!(<Module<Runtime>>::register_operator(Origin::signed(1)).is_ok());
-			assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
-			<Module<Runtime> as OnFinalize<u64>>::on_finalize(20);
+			publish_specification(1);
+			assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, None, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+			<Module<Runtime> as OnInitialize<u64>>::on_initialize(12);
 			// Request has been killed, too old
 			assert!(<Module<Runtime>>::callback(Origin::signed(1), 0, 10.encode()).is_err());
 		});
 
 	}
 
+	#[test]
+	fn fees_are_transferred_to_operators() {
+
+		new_test_ext().execute_with(|| {
+			assert!(<Module<Runtime>>::register_operator(Origin::signed(1)).is_ok());
+			publish_specification(1);
+			assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, None, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+			assert_eq!(Balances::free_balance(2), 18);
+			assert_eq!(Balances::reserved_balance(2), 2);
+
+			assert!(<Module<Runtime>>::callback(Origin::signed(1), 0, 10u128.encode()).is_ok());
+			// Operator bond is still reserved
+			assert_eq!(Balances::free_balance(1), 7);
+			assert_eq!(Balances::free_balance(2), 18);
+			assert_eq!(Balances::reserved_balance(2), 0);
+		});
+
+	}
+
+	#[test]
+	fn expired_requests_are_refunded() {
+
+		new_test_ext().execute_with(|| {
+			assert!(<Module<Runtime>>::register_operator(Origin::signed(1)).is_ok());
+			publish_specification(1);
+			assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, None, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+
+			// Still valid
+			<Module<Runtime> as OnInitialize<u64>>::on_initialize(11);
+			assert_eq!(Balances::reserved_balance(2), 2);
+			assert_eq!(module2::Failures::get(), 0);
+
+			<Module<Runtime> as OnInitialize<u64>>::on_initialize(12);
+			assert!(<Module<Runtime>>::request(0).is_none());
+			assert_eq!(Balances::free_balance(1), 5);
+			assert_eq!(Balances::free_balance(2), 20);
+			// Operator has been slashed for missing the request
+			assert_eq!(Balances::reserved_balance(1), 4);
+			assert_eq!(<Module<Runtime>>::operator_bond(1), 4);
+			assert_eq!(Balances::reserved_balance(2), 0);
+			// Originator has been notified
+			assert_eq!(module2::Failures::get(), 1);
+			assert_eq!(
+				System::events().last().unwrap().event,
+				TestEvent::chainlink(RawEvent::KillRequest(0))
+			);
+		});
+
+	}
+
+	#[test]
+	fn requests_can_have_their_own_deadline() {
+
+		new_test_ext().execute_with(|| {
+			register_operators(&[1]);
+			let callback: Call = module2::Call::<Runtime>::callback(vec![]).into();
+			assert_eq!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, Some(0), callback.clone()), Err(Error::<Runtime>::InvalidValidityPeriod.into()));
+			assert_eq!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, Some(21), callback.clone()), Err(Error::<Runtime>::InvalidValidityPeriod.into()));
+			assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, Some(2), callback.clone()).is_ok());
+			assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, Some(20), callback).is_ok());
+			assert_eq!(<Module<Runtime>>::request(0).unwrap().deadline, 3);
+			assert_eq!(<Module<Runtime>>::request(1).unwrap().deadline, 21);
+
+			System::set_block_number(4);
+			<Module<Runtime> as OnInitialize<u64>>::on_initialize(4);
+			assert!(<Module<Runtime>>::request(0).is_none());
+			assert_eq!(<Module<Runtime>>::callback(Origin::signed(1), 0, 10u128.encode()), Err(Error::<Runtime>::DeadlinePassed.into()));
+
+			// Still running after the default validity period
+			System::set_block_number(21);
+			<Module<Runtime> as OnInitialize<u64>>::on_initialize(12);
+			assert!(<Module<Runtime>>::callback(Origin::signed(1), 1, 10u128.encode()).is_ok());
+			assert_eq!(module2::Result::get(), 10);
+		});
+
+		new_test_ext().execute_with(|| {
+			register_operators(&[1]);
+			assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, Some(2), module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+
+			// Not killed yet, but too late anyway
+			System::set_block_number(4);
+			assert_eq!(<Module<Runtime>>::callback(Origin::signed(1), 0, 10u128.encode()), Err(Error::<Runtime>::DeadlinePassed.into()));
+		});
+
+		new_test_ext().execute_with(|| {
+			register_operators(&[1, 2]);
+			let callback: Call = module2::Call::<Runtime>::callback(vec![]).into();
+			assert_eq!(<Module<Runtime>>::initiate_aggregated_request(Origin::signed(3), vec![1, 2], vec![], 1, vec![], 2, Some(21), AggregationMode::Median, 2, callback.clone()), Err(Error::<Runtime>::InvalidValidityPeriod.into()));
+			assert!(<Module<Runtime>>::initiate_aggregated_request(Origin::signed(3), vec![1, 2], vec![], 1, vec![], 2, Some(2), AggregationMode::Median, 2, callback).is_ok());
+			// Aggregated request is 0, sub requests are 1 and 2
+			assert_eq!(<Module<Runtime>>::request(1).unwrap().deadline, 3);
+			assert_eq!(<Module<Runtime>>::request(2).unwrap().deadline, 3);
+		});
+
+	}
+
+	#[test]
+	fn only_expiring_requests_are_considered() {
+
+		new_test_ext().execute_with(|| {
+			assert!(<Module<Runtime>>::register_operator(Origin::signed(1)).is_ok());
+			publish_specification(1);
+			assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, None, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+			assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, None, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+			assert_eq!(<Module<Runtime>>::expiring_requests(12), vec![0, 1]);
+
+			// Nothing expires
+			assert_eq!(<Module<Runtime> as OnInitialize<u64>>::on_initialize(11), 1 + 10);
+
+			// Answered requests are skipped
+			assert!(<Module<Runtime>>::callback(Origin::signed(1), 0, 10u128.encode()).is_ok());
+			let weight = <Module<Runtime> as OnInitialize<u64>>::on_initialize(12);
+			assert!(weight > 1 + 10 + 2);
+			assert!(<Module<Runtime>>::request(1).is_none());
+			assert!(<Module<Runtime>>::expiring_requests(12).is_empty());
+			assert_eq!(
+				System::events().iter().filter(|record| record.event == TestEvent::chainlink(RawEvent::KillRequest(0))).count(),
+				0
+			);
+		});
+
+	}
+
+	#[test]
+	fn running_requests_are_indexed_on_upgrade() {
+
+		new_test_ext().execute_with(|| {
+			System::set_block_number(30);
+			let request = |block_number| OldRequest { operator: 1, requester: 2, callback: module2::Call::<Runtime>::callback(vec![]).into(), block_number, fee: 2u32 };
+			put_old_request(0, request(25));
+			put_old_request(1, request(5));
+			StorageVersion::put(Releases::V2);
+
+			<Module<Runtime> as OnRuntimeUpgrade>::on_runtime_upgrade();
+
+			assert_eq!(StorageVersion::get(), Releases::V6);
+			assert_eq!(<Module<Runtime>>::expiring_requests(36), vec![0]);
+			// Already expired, killed in the next block
+			assert_eq!(<Module<Runtime>>::expiring_requests(31), vec![1]);
+		});
+
+	}
+
+	#[test]
+	fn legacy_requests_are_killed_on_upgrade_and_refunded_by_root() {
+
+		new_test_ext().execute_with(|| {
+			// `callback(vec![])` of the pricefeed `Call` of the legacy runtimes, which doesn't decode as a `Call` of this runtime
+			let legacy_callback = (2u8, Vec::<u8>::new());
+			assert!(Balances::reserve(&2, 3).is_ok());
+			put_storage_value(b"Chainlink", b"Requests", &Twox64Concat::hash(&0u64.encode()), (1u64, vec![legacy_callback], 0u64, 3u32));
+
+			<Module<Runtime> as OnRuntimeUpgrade>::on_runtime_upgrade();
+
+			assert_eq!(StorageVersion::get(), Releases::V6);
+			assert!(StorageIterator::<RawValue>::new(b"Chainlink", b"Requests").next().is_none());
+			assert_eq!(<Module<Runtime>>::legacy_request_fee(0), Some(3));
+
+			assert!(<Module<Runtime>>::refund_legacy_request(Origin::signed(2), 0, 2).is_err());
+			assert!(<Module<Runtime>>::refund_legacy_request(Origin::root(), 0, 2).is_ok());
+			assert_eq!(Balances::reserved_balance(2), 0);
+			assert_eq!(<Module<Runtime>>::legacy_request_fee(0), None);
+			assert_eq!(<Module<Runtime>>::refund_legacy_request(Origin::root(), 0, 2), Err(Error::<Runtime>::UnknownRequest.into()));
+		});
+
+	}
+
+	// Stores a request as encoded before fees were typed as `BalanceOf<T>`
+	fn put_old_request(request_id: RequestIdentifier, request: OldRequest<u64, Call, u64, u32>) {
+		put_storage_value(b"Chainlink", b"Requests", &Twox64Concat::hash(&request_id.encode()), request);
+	}
+
+	#[test]
+	fn fees_are_converted_on_upgrade() {
+
+		new_test_ext().execute_with(|| {
+			put_old_request(0, OldRequest { operator: 1, requester: 2, callback: module2::Call::<Runtime>::callback(vec![]).into(), block_number: 0, fee: 2u32 });
+			let specification_key = [Twox64Concat::hash(&1u64.encode()), Blake2_128Concat::hash(&Vec::<u8>::new().encode())].concat();
+			put_storage_value(b"Chainlink", b"Specifications", &specification_key, Specification { min_fee: 3u32, data_versions: vec![1] });
+			StorageVersion::put(Releases::V3);
+
+			<Module<Runtime> as OnRuntimeUpgrade>::on_runtime_upgrade();
+
+			assert_eq!(StorageVersion::get(), Releases::V6);
+			assert_eq!(<Module<Runtime>>::request(0).unwrap().fee, 2u64);
+			assert_eq!(<Module<Runtime>>::request(0).unwrap().deadline, 10);
+			// Counted as pending, without deposit
+			assert_eq!(<Module<Runtime>>::pending_requests(2), 1);
+			assert_eq!(<Module<Runtime>>::pending_request_count(), 1);
+			assert_eq!(<Module<Runtime>>::request_deposit(0), 0);
+			assert_eq!(<Module<Runtime>>::specification(1, vec![]), Some(Specification { min_fee: 3u64, data_versions: vec![1] }));
+		});
+
+	}
+
+	fn register_operators(operators: &[u64]) {
+		for operator in operators {
+			assert!(<Module<Runtime>>::register_operator(Origin::signed(*operator)).is_ok());
+			publish_specification(*operator);
+		}
+	}
+
+	#[test]
+	fn aggregated_requests_are_validated() {
+
+		new_test_ext().execute_with(|| {
+			register_operators(&[1, 2]);
+			let callback: Call = module2::Call::<Runtime>::callback(vec![]).into();
+			assert_eq!(<Module<Runtime>>::initiate_aggregated_request(Origin::signed(4), vec![1, 2], vec![], 1, vec![], 1, None, AggregationMode::Median, 0, callback.clone()), Err(Error::<Runtime>::InvalidThreshold.into()));
+			assert_eq!(<Module<Runtime>>::initiate_aggregated_request(Origin::signed(4), vec![1, 2], vec![], 1, vec![], 1, None, AggregationMode::Median, 3, callback.clone()), Err(Error::<Runtime>::InvalidThreshold.into()));
+			assert_eq!(<Module<Runtime>>::initiate_aggregated_request(Origin::signed(4), vec![1, 1], vec![], 1, vec![], 1, None, AggregationMode::Median, 2, callback.clone()), Err(Error::<Runtime>::DuplicateOperator.into()));
+			assert_eq!(<Module<Runtime>>::initiate_aggregated_request(Origin::signed(4), vec![1, 3], vec![], 1, vec![], 1, None, AggregationMode::Median, 2, callback.clone()), Err(Error::<Runtime>::UnknownOperator.into()));
+			assert_eq!(<Module<Runtime>>::initiate_aggregated_request(Origin::signed(4), vec![1, 2], vec![], 1, vec![], 0, None, AggregationMode::Median, 2, callback), Err(Error::<Runtime>::InsufficientFee.into()));
+		});
+
+	}
+
+	#[test]
+	fn aggregated_requests_dispatch_median() {
+
+		new_test_ext().execute_with(|| {
+			register_operators(&[1, 2, 3]);
+			assert!(<Module<Runtime>>::initiate_aggregated_request(Origin::signed(4), vec![1, 2, 3], vec![], 1, vec![], 2, None, AggregationMode::Median, 3, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+			// Aggregated request is 0, sub requests are 1, 2 and 3
+			assert_eq!(Balances::reserved_balance(4), 6);
+			assert_eq!(<Module<Runtime>>::aggregation(0).unwrap().pending, vec![1, 2, 3]);
+
+			// Only one sub request can be answered by each operator
+			assert_eq!(<Module<Runtime>>::callback(Origin::signed(1), 2, 10i128.encode()), Err(Error::<Runtime>::WrongOperator.into()));
+			// Median needs numeric answers
+			assert_eq!(<Module<Runtime>>::callback(Origin::signed(1), 1, vec![1]), Err(Error::<Runtime>::InvalidAggregatedResult.into()));
+			assert_eq!(<Module<Runtime>>::callback(Origin::signed(1), 1, (10i128, 0u8).encode()), Err(Error::<Runtime>::InvalidAggregatedResult.into()));
+
+			assert!(<Module<Runtime>>::callback(Origin::signed(1), 1, 10i128.encode()).is_ok());
+			assert!(<Module<Runtime>>::callback(Origin::signed(2), 2, 1000i128.encode()).is_ok());
+			// Not enough answers yet
+			assert_eq!(module2::Result::get(), 0);
+
+			assert!(<Module<Runtime>>::callback(Origin::signed(3), 3, 12i128.encode()).is_ok());
+			assert_eq!(module2::Result::get(), 12);
+			assert_eq!(
+				System::events().last().unwrap().event,
+				TestEvent::chainlink(RawEvent::OracleAnswer(3, 3, 3, 12i128.encode(), 2))
+			);
+			assert!(<Module<Runtime>>::aggregation(0).is_none());
+			assert!(<Module<Runtime>>::aggregated_request_of(3).is_none());
+		});
+
+	}
+
+	#[test]
+	fn aggregated_requests_dispatch_quorum() {
+
+		new_test_ext().execute_with(|| {
+			register_operators(&[1, 2, 3]);
+			assert!(<Module<Runtime>>::initiate_aggregated_request(Origin::signed(4), vec![1, 2, 3], vec![], 1, vec![], 2, None, AggregationMode::Quorum, 2, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+
+			assert!(<Module<Runtime>>::callback(Origin::signed(1), 1, 7u128.encode()).is_ok());
+			assert!(<Module<Runtime>>::callback(Origin::signed(2), 2, 8u128.encode()).is_ok());
+			assert_eq!(module2::Result::get(), 0);
+
+			assert!(<Module<Runtime>>::callback(Origin::signed(3), 3, 8u128.encode()).is_ok());
+			assert_eq!(module2::Result::get(), 8);
+		});
+
+		new_test_ext().execute_with(|| {
+			register_operators(&[1, 2, 3]);
+			assert!(<Module<Runtime>>::initiate_aggregated_request(Origin::signed(4), vec![1, 2, 3], vec![], 1, vec![], 2, None, AggregationMode::Quorum, 2, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+
+			assert!(<Module<Runtime>>::callback(Origin::signed(1), 1, 7u128.encode()).is_ok());
+			assert!(<Module<Runtime>>::callback(Origin::signed(2), 2, 7u128.encode()).is_ok());
+			assert_eq!(module2::Result::get(), 7);
+
+			// Remaining sub request has been dropped, its fee unreserved
+			assert!(!<Requests<Runtime>>::contains_key(3));
+			assert_eq!(<Module<Runtime>>::callback(Origin::signed(3), 3, 7u128.encode()), Err(Error::<Runtime>::UnknownRequest.into()));
+			assert_eq!(Balances::reserved_balance(4), 0);
+			assert_eq!(Balances::free_balance(4), 36);
+		});
+
+	}
+
+	#[test]
+	fn aggregated_requests_fail_without_enough_answers() {
+
+		new_test_ext().execute_with(|| {
+			register_operators(&[1, 2]);
+			assert!(<Module<Runtime>>::initiate_aggregated_request(Origin::signed(4), vec![1, 2], vec![], 1, vec![], 2, None, AggregationMode::Mean, 2, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+			assert!(<Module<Runtime>>::callback(Origin::signed(1), 1, 10i128.encode()).is_ok());
+
+			<Module<Runtime> as OnInitialize<u64>>::on_initialize(12);
+
+			assert!(<Module<Runtime>>::aggregation(0).is_none());
+			assert_eq!(
+				System::events().last().unwrap().event,
+				TestEvent::chainlink(RawEvent::AggregationFailed(0))
+			);
+			assert_eq!(module2::Result::get(), 0);
+			// Only the aggregated request failure is notified
+			assert_eq!(module2::Failures::get(), 1);
+			assert_eq!(Balances::reserved_balance(4), 0);
+		});
+
+	}
+
+	#[test]
+	fn aggregation_mean_and_even_median() {
+		let aggregation = |mode, answers: Vec<i128>| Aggregation {
+			mode,
+			threshold: answers.len() as u32,
+			pending: vec![],
+			answers: answers.into_iter().map(|answer| (0u64, answer.encode())).collect(),
+		};
+
+		assert_eq!(aggregation(AggregationMode::Mean, vec![10, 20, 60]).aggregated_result(), Some(30i128.encode()));
+		assert_eq!(aggregation(AggregationMode::Median, vec![-3, 10, 1, 100]).aggregated_result(), Some(5i128.encode()));
+		assert_eq!(aggregation(AggregationMode::Median, vec![]).aggregated_result(), None);
+		// Answers with trailing bytes are ignored
+		let mut malformed = aggregation(AggregationMode::Mean, vec![10, 20]);
+		malformed.answers[1].1.push(0);
+		assert_eq!(malformed.aggregated_result(), Some(10i128.encode()));
+	}
+
+	#[test]
+	fn request_lifecycle_is_recorded() {
+
+		new_test_ext().execute_with(|| {
+			register_operators(&[1]);
+			let callback: Call = module2::Call::<Runtime>::callback(vec![]).into();
+			for _ in 0..3 {
+				assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, None, callback.clone()).is_ok());
+			}
+			assert_eq!(<Module<Runtime>>::request_status(0), Some(RequestRecord { requester: 2, operator: Some(1), block_number: 1, status: RequestStatus::Pending }));
+
+			System::set_block_number(2);
+			assert!(<Module<Runtime>>::callback(Origin::signed(1), 0, 10u128.encode()).is_ok());
+			// Not a valid `u128`, the callback fails
+			assert!(<Module<Runtime>>::callback(Origin::signed(1), 1, vec![1]).is_ok());
+
+			System::set_block_number(12);
+			<Module<Runtime> as OnInitialize<u64>>::on_initialize(12);
+
+			let statuses: Vec<_> = <Module<Runtime>>::requests_of(2).into_iter().map(|(request_id, record)| (request_id, record.status)).collect();
+			assert_eq!(statuses, vec![
+				(0, RequestStatus::Answered(2)),
+				(1, RequestStatus::CallbackFailed(2)),
+				(2, RequestStatus::Expired(12)),
+			]);
+		});
+
+	}
+
+	#[test]
+	fn request_history_is_bounded() {
+
+		new_test_ext().execute_with(|| {
+			register_operators(&[1]);
+			for _ in 0..5 {
+				assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, None, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+			}
+
+			// Only the last `HistoryDepth` requests are kept
+			assert_eq!(<Module<Runtime>>::requester_history(2), vec![1, 2, 3, 4]);
+			assert!(<Module<Runtime>>::request_status(0).is_none());
+			// Still running though
+			assert!(<Module<Runtime>>::request(0).is_some());
+			assert!(<Module<Runtime>>::callback(Origin::signed(1), 0, 10u128.encode()).is_ok());
+			assert!(<Module<Runtime>>::request_status(0).is_none());
+		});
+
+	}
+
+	#[test]
+	fn aggregated_request_lifecycle_is_recorded() {
+
+		new_test_ext().execute_with(|| {
+			register_operators(&[1, 2, 3]);
+			assert!(<Module<Runtime>>::initiate_aggregated_request(Origin::signed(4), vec![1, 2, 3], vec![], 1, vec![], 2, None, AggregationMode::Median, 2, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+			assert_eq!(<Module<Runtime>>::request_status(0).unwrap().operator, None);
+
+			assert!(<Module<Runtime>>::callback(Origin::signed(1), 1, 10i128.encode()).is_ok());
+			assert!(<Module<Runtime>>::callback(Origin::signed(2), 2, 20i128.encode()).is_ok());
+
+			let statuses: Vec<_> = <Module<Runtime>>::requests_of(4).into_iter().map(|(request_id, record)| (request_id, record.status)).collect();
+			assert_eq!(statuses, vec![
+				(0, RequestStatus::Answered(1)),
+				(1, RequestStatus::Answered(1)),
+				(2, RequestStatus::Answered(1)),
+				(3, RequestStatus::Dropped(1)),
+			]);
+		});
+
+	}
+
+	// Initiate a request answered by an invalid `u128`, which module2 fails to decode
+	fn fail_callback(policy: CallbackFailurePolicy) {
+		CALLBACK_FAILURE_POLICY.with(|p| *p.borrow_mut() = policy);
+		register_operators(&[1]);
+		assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, None, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+		assert!(<Module<Runtime>>::callback(Origin::signed(1), 0, vec![1]).is_ok());
+		assert!(System::events().iter().any(|record| match record.event {
+			TestEvent::chainlink(RawEvent::CallbackFailed(0, _)) => true,
+			_ => false,
+		}));
+	}
+
+	#[test]
+	fn callback_failures_can_pay_operator() {
+
+		new_test_ext().execute_with(|| {
+			fail_callback(CallbackFailurePolicy::PayOperator);
+
+			assert!(<Module<Runtime>>::request(0).is_none());
+			assert_eq!(Balances::free_balance(1), 7);
+			assert_eq!(Balances::reserved_balance(2), 0);
+			assert_eq!(
+				System::events().last().unwrap().event,
+				TestEvent::chainlink(RawEvent::OracleAnswer(1, 0, 1, vec![1], 2))
+			);
+		});
+
+	}
+
+	#[test]
+	fn callback_failures_can_refund_requester() {
+
+		new_test_ext().execute_with(|| {
+			fail_callback(CallbackFailurePolicy::RefundRequester);
+
+			assert!(<Module<Runtime>>::request(0).is_none());
+			assert_eq!(Balances::free_balance(1), 5);
+			assert_eq!(Balances::free_balance(2), 20);
+			assert_eq!(<Module<Runtime>>::request_status(0).unwrap().status, RequestStatus::CallbackFailed(1));
+		});
+
+	}
+
+	#[test]
+	fn callback_failures_can_keep_request_open() {
+
+		new_test_ext().execute_with(|| {
+			fail_callback(CallbackFailurePolicy::KeepOpen);
+
+			assert!(<Module<Runtime>>::request(0).is_some());
+			assert_eq!(Balances::reserved_balance(2), 2);
+
+			// Operator can provide a valid result
+			assert!(<Module<Runtime>>::callback(Origin::signed(1), 0, 10u128.encode()).is_ok());
+			assert_eq!(module2::Result::get(), 10);
+			assert_eq!(Balances::free_balance(1), 7);
+			assert_eq!(<Module<Runtime>>::request_status(0).unwrap().status, RequestStatus::Answered(1));
+			assert!(!<Module<Runtime>>::answered_request(0));
+		});
+
+	}
+
+	#[test]
+	fn requests_kept_open_dont_slash_operators_that_answered() {
+
+		new_test_ext().execute_with(|| {
+			fail_callback(CallbackFailurePolicy::KeepOpen);
+			assert!(<Module<Runtime>>::answered_request(0));
+
+			<Module<Runtime> as OnInitialize<u64>>::on_initialize(12);
+			assert!(<Module<Runtime>>::request(0).is_none());
+			assert!(!<Module<Runtime>>::answered_request(0));
+			assert_eq!(Balances::free_balance(2), 20);
+			assert_eq!(<Module<Runtime>>::operator_bond(1), 5);
+			assert_eq!(<Module<Runtime>>::operator_stats(1).expired, 0);
+			assert_eq!(<Module<Runtime>>::request_status(0).unwrap().status, RequestStatus::CallbackFailed(1));
+		});
+
+	}
+
+	#[test]
+	fn requests_can_be_cancelled() {
+
+		new_test_ext().execute_with(|| {
+			register_operators(&[1]);
+			assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, None, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+
+			assert_eq!(<Module<Runtime>>::cancel_request(Origin::signed(2), 0), Err(Error::<Runtime>::CancellationGracePeriod.into()));
+			System::set_block_number(4);
+			assert_eq!(<Module<Runtime>>::cancel_request(Origin::signed(3), 0), Err(Error::<Runtime>::NotRequester.into()));
+			assert_eq!(<Module<Runtime>>::cancel_request(Origin::signed(2), 1), Err(Error::<Runtime>::UnknownRequest.into()));
+
+			assert!(<Module<Runtime>>::cancel_request(Origin::signed(2), 0).is_ok());
+			assert!(<Module<Runtime>>::request(0).is_none());
+			assert_eq!(Balances::free_balance(2), 20);
+			assert_eq!(<Module<Runtime>>::request_status(0).unwrap().status, RequestStatus::Cancelled(4));
+			assert_eq!(
+				System::events().last().unwrap().event,
+				TestEvent::chainlink(RawEvent::RequestCancelled(0))
+			);
+
+			// Too late to answer
+			assert_eq!(<Module<Runtime>>::callback(Origin::signed(1), 0, 10u128.encode()), Err(Error::<Runtime>::UnknownRequest.into()));
+		});
+
+	}
+
+	#[test]
+	fn aggregated_requests_can_be_cancelled() {
+
+		new_test_ext().execute_with(|| {
+			register_operators(&[1, 2]);
+			assert!(<Module<Runtime>>::initiate_aggregated_request(Origin::signed(4), vec![1, 2], vec![], 1, vec![], 2, None, AggregationMode::Median, 2, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+			assert!(<Module<Runtime>>::callback(Origin::signed(1), 1, 10i128.encode()).is_ok());
+			System::set_block_number(4);
+
+			assert_eq!(<Module<Runtime>>::cancel_request(Origin::signed(4), 2), Err(Error::<Runtime>::SubRequestNotCancellable.into()));
+			assert!(<Module<Runtime>>::cancel_request(Origin::signed(4), 0).is_ok());
+			assert!(<Module<Runtime>>::aggregation(0).is_none());
+			assert!(<Module<Runtime>>::request(2).is_none());
+			// Only the fee of the unanswered sub request is given back
+			assert_eq!(Balances::free_balance(4), 38);
+			assert_eq!(Balances::reserved_balance(4), 0);
+		});
+
+	}
+
+	#[test]
+	fn results_are_dispatched_to_any_consumer() {
+
+		new_test_ext().execute_with(|| {
+			register_operators(&[1]);
+			assert!(<Module<Runtime>>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, None, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+			assert!(<Module<Runtime>>::initiate_request(Origin::signed(3), 1, vec![], 1, vec![], 2, None, module3::Call::<Runtime>::store(vec![]).into()).is_ok());
+			// Calls that can't receive a result are refused
+			assert_eq!(<Module<Runtime>>::initiate_request(Origin::signed(3), 1, vec![], 1, vec![], 2, None, module2::Call::<Runtime>::failure().into()), Err(Error::<Runtime>::UnknownCallback.into()));
+
+			assert!(<Module<Runtime>>::callback(Origin::signed(1), 0, 10u128.encode()).is_ok());
+			assert!(<Module<Runtime>>::callback(Origin::signed(1), 1, b"ETH".to_vec()).is_ok());
+
+			assert_eq!(module2::Result::get(), 10);
+			assert_eq!(module3::Result::get(), b"ETH".to_vec());
+		});
+
+	}
+
+	#[test]
+	fn callbacks_are_limited_to_max_callback_weight() {
+
+		new_test_ext().execute_with(|| {
+			register_operators(&[1]);
+			assert_eq!(<Module<Runtime>>::initiate_request(Origin::signed(3), 1, vec![], 1, vec![], 2, None, module3::Call::<Runtime>::store(vec![0; 101]).into()), Err(Error::<Runtime>::CallbackTooHeavy.into()));
+			assert!(<Module<Runtime>>::initiate_request(Origin::signed(3), 1, vec![], 1, vec![], 2, None, module3::Call::<Runtime>::store(vec![]).into()).is_ok());
+
+			// A result making the callback too heavy is not dispatched
+			assert!(<Module<Runtime>>::callback(Origin::signed(1), 0, vec![0; 101]).is_ok());
+			assert!(module3::Result::get().is_empty());
+			assert!(System::events().iter().any(|record| record.event == TestEvent::chainlink(RawEvent::CallbackFailed(0, Error::<Runtime>::CallbackTooHeavy.into()))));
+			assert_eq!(<Module<Runtime>>::request_status(0).unwrap().status, RequestStatus::CallbackFailed(1));
+		});
+
+	}
+
+	#[test]
+	fn operators_statistics_are_recorded() {
+
+		new_test_ext().execute_with(|| {
+			register_operators(&[1, 2, 3]);
+			for operator in &[1, 1, 2, 2, 3] {
+				assert!(<Module<Runtime>>::initiate_request(Origin::signed(4), *operator, vec![], 1, vec![], 2, None, module2::Call::<Runtime>::callback(vec![]).into()).is_ok());
+			}
+
+			System::set_block_number(3);
+			assert!(<Module<Runtime>>::callback(Origin::signed(1), 0, 10u128.encode()).is_ok());
+			assert!(<Module<Runtime>>::callback(Origin::signed(2), 2, 10u128.encode()).is_ok());
+			assert!(<Module<Runtime>>::callback(Origin::signed(2), 3, 10u128.encode()).is_ok());
+			System::set_block_number(5);
+			assert!(<Module<Runtime>>::callback(Origin::signed(3), 4, 10u128.encode()).is_ok());
+			System::set_block_number(12);
+			<Module<Runtime> as OnInitialize<u64>>::on_initialize(12);
+
+			let stats = <Module<Runtime>>::operator_stats(1);
+			assert_eq!(stats, OperatorStats { assigned: 2, answered: 1, expired: 1, total_latency: 2, earned: 2 });
+			assert_eq!(stats.mean_latency(), Some(2));
+			assert_eq!(<Module<Runtime>>::operator_stats(2).earned, 4);
+
+			// 2 answered all its requests, 3 too but slower, 1 missed one
+			let leaderboard: Vec<u64> = <Module<Runtime>>::leaderboard().into_iter().map(|(operator, _)| operator).collect();
+			assert_eq!(leaderboard, vec![2, 3, 1]);
+			assert_eq!(<Module<Runtime>>::best_operator(&vec![]), Some(2));
+			assert_eq!(<Module<Runtime>>::best_operator(&vec![1]), None);
+		});
+
+	}
+
+	#[test]
+	fn pending_requests_are_limited() {
+
+		new_test_ext().execute_with(|| {
+			REQUEST_DEPOSIT.with(|deposit| *deposit.borrow_mut() = 1);
+			register_operators(&[1, 2]);
+			let callback: Call = module2::Call::<Runtime>::callback(vec![]).into();
+			for _ in 0..4 {
+				assert!(<Module<Runtime>>::initiate_request(Origin::signed(4), 1, vec![], 1, vec![], 2, None, callback.clone()).is_ok());
+			}
+			// Fees and deposits are reserved
+			assert_eq!(Balances::reserved_balance(4), 12);
+			assert_eq!(<Module<Runtime>>::pending_requests(4), 4);
+			assert_eq!(<Module<Runtime>>::initiate_aggregated_request(Origin::signed(4), vec![1, 2], vec![], 1, vec![], 2, None, AggregationMode::Median, 2, callback.clone()), Err(Error::<Runtime>::TooManyPendingRequests.into()));
+			assert!(<Module<Runtime>>::initiate_request(Origin::signed(4), 1, vec![], 1, vec![], 2, None, callback.clone()).is_ok());
+			assert_eq!(<Module<Runtime>>::initiate_request(Origin::signed(4), 1, vec![], 1, vec![], 2, None, callback.clone()), Err(Error::<Runtime>::TooManyPendingRequests.into()));
+
+			assert!(<Module<Runtime>>::initiate_aggregated_request(Origin::signed(3), vec![1, 2], vec![], 1, vec![], 2, None, AggregationMode::Median, 2, callback.clone()).is_ok());
+			assert_eq!(<Module<Runtime>>::pending_request_count(), 7);
+			assert_eq!(<Module<Runtime>>::initiate_aggregated_request(Origin::signed(3), vec![1, 2], vec![], 1, vec![], 2, None, AggregationMode::Median, 2, callback.clone()), Err(Error::<Runtime>::PendingRequestsLimitReached.into()));
+
+			// Deposits are given back once requests are answered, cancelled or killed
+			assert!(<Module<Runtime>>::callback(Origin::signed(1), 0, 10u128.encode()).is_ok());
+			assert_eq!(Balances::reserved_balance(4), 12);
+			System::set_block_number(4);
+			assert!(<Module<Runtime>>::cancel_request(Origin::signed(4), 1).is_ok());
+			assert_eq!(Balances::reserved_balance(4), 9);
+			<Module<Runtime> as OnInitialize<u64>>::on_initialize(12);
+			assert_eq!(Balances::reserved_balance(4), 0);
+			assert_eq!(Balances::free_balance(4), 38);
+			assert_eq!(Balances::reserved_balance(3), 0);
+			assert_eq!(<Module<Runtime>>::pending_requests(4), 0);
+			assert_eq!(<Module<Runtime>>::pending_request_count(), 0);
+		});
+
+	}
+
 }
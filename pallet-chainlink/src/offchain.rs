@@ -0,0 +1,291 @@
+//! # An Operator running inside the node, as an offchain worker
+//!
+//! Available with the `offchain-operator` feature. Once added to the runtime, each node having an Operator key
+//! (of type `KEY_TYPE`) in its keystore answers the requests assigned to this Operator:
+//! the request parameters are sent to an external adapter, and its result is submitted back with a signed `callback` transaction.
+//!
+//! Only requests built with `RequestParameters` are supported. The adapter receives a Chainlink adapter request
+//! (`{"id": "<request id>", "data": {<parameters>}}`) and must answer with a `result` field, encoded as requested by the `resultType` parameter.
+//! A `price` result also needs a `decimals` field.
+
+use core::convert::TryInto;
+use codec::Encode;
+use frame_support::{debug, decl_module, traits::Get};
+use frame_system::offchain::{AppCrypto, CreateSignedTransaction, SendSignedTransaction, Signer};
+use lite_json::json::JsonValue;
+use sp_core::crypto::KeyTypeId;
+use sp_runtime::{RuntimeAppPublic, offchain::{http, Duration, StorageKind}, traits::IdentifyAccount};
+use sp_std::prelude::*;
+
+use crate::{RawEvent, RequestIdentifier, DataVersion, encoding::{self, RequestParameters}};
+
+// The key type of Operators keys
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"chlk");
+
+// The persistent offchain storage key holding the adapter URL, overriding `Trait::AdapterUrl`
+pub const ADAPTER_URL_KEY: &[u8] = b"chainlink::adapter-url";
+
+// Time given to the adapter to answer, in milliseconds
+const ADAPTER_TIMEOUT: u64 = 5_000;
+
+// The crypto used by Operators keys
+pub mod crypto {
+	use super::KEY_TYPE;
+	use sp_core::sr25519::Signature as Sr25519Signature;
+	use sp_runtime::{app_crypto::{app_crypto, sr25519}, traits::Verify, MultiSignature, MultiSigner};
+
+	app_crypto!(sr25519, KEY_TYPE);
+
+	pub struct OperatorId;
+
+	impl frame_system::offchain::AppCrypto<MultiSigner, MultiSignature> for OperatorId {
+		type RuntimeAppPublic = Public;
+		type GenericSignature = sp_core::sr25519::Signature;
+		type GenericPublic = sp_core::sr25519::Public;
+	}
+
+	impl frame_system::offchain::AppCrypto<<Sr25519Signature as Verify>::Signer, Sr25519Signature> for OperatorId {
+		type RuntimeAppPublic = Public;
+		type GenericSignature = sp_core::sr25519::Signature;
+		type GenericPublic = sp_core::sr25519::Public;
+	}
+}
+
+pub trait Trait: crate::Trait + CreateSignedTransaction<crate::Call<Self>> {
+	// The keys Operators sign their answers with
+	type AuthorityId: AppCrypto<Self::Public, Self::Signature>;
+
+	// The runtime `Event`, to find the requests initiated in a block
+	type RuntimeEvent: From<<Self as frame_system::Trait>::Event> + TryInto<crate::Event<Self>>;
+
+	// The adapter requests are sent to, unless overridden in the offchain storage
+	type AdapterUrl: Get<&'static str>;
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+
+		// Answer the requests initiated in this block and assigned to a local Operator
+		fn offchain_worker(_block_number: T::BlockNumber) {
+			let operators = Self::local_operators();
+			if operators.is_empty() {
+				return;
+			}
+
+			for record in frame_system::Module::<T>::events() {
+				let event: Result<crate::Event<T>, _> = T::RuntimeEvent::from(record.event).try_into();
+				if let Ok(RawEvent::OracleRequest(operator, _, request_id, _, data_version, data, _, _)) = event {
+					if let Some((_, public)) = operators.iter().find(|(account, _)| *account == operator) {
+						if let Err(error) = Self::answer(public.clone(), request_id, data_version, &data) {
+							debug::warn!("Chainlink request {} not answered: {}", request_id, error);
+						}
+					}
+				}
+			}
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+
+	// The accounts of the Operators keys available in the local keystore
+	fn local_operators() -> Vec<(T::AccountId, T::Public)> {
+		<T::AuthorityId as AppCrypto<T::Public, T::Signature>>::RuntimeAppPublic::all()
+			.into_iter()
+			.map(|key| {
+				let generic_public = <T::AuthorityId as AppCrypto<T::Public, T::Signature>>::GenericPublic::from(key);
+				let public: T::Public = generic_public.into();
+				(public.clone().into_account(), public)
+			})
+			.collect()
+	}
+
+	// Fetch the result of a request from the adapter, and submit it signed by the Operator
+	fn answer(operator: T::Public, request_id: RequestIdentifier, data_version: DataVersion, data: &[u8]) -> Result<(), &'static str> {
+		let parameters = decode_parameters(data_version, data)?;
+		let response = Self::fetch(&request_body(request_id, &parameters))?;
+		let result = encode_result(&parameters, &response)?;
+
+		let signer = Signer::<T, T::AuthorityId>::all_accounts().with_filter(vec![operator]);
+		match signer.send_signed_transaction(|_| crate::Call::callback(request_id, result.clone())).pop() {
+			Some((_, Ok(()))) => Ok(()),
+			_ => Err("Callback transaction not submitted"),
+		}
+	}
+
+	// POST a request to the adapter, returning the response body
+	fn fetch(body: &[u8]) -> Result<Vec<u8>, &'static str> {
+		let url = sp_io::offchain::local_storage_get(StorageKind::PERSISTENT, ADAPTER_URL_KEY)
+			.unwrap_or_else(|| T::AdapterUrl::get().as_bytes().to_vec());
+		let url = core::str::from_utf8(&url).map_err(|_| "Invalid adapter URL")?;
+
+		let deadline = sp_io::offchain::timestamp().add(Duration::from_millis(ADAPTER_TIMEOUT));
+		let pending = http::Request::post(url, vec![body])
+			.add_header("Content-Type", "application/json")
+			.deadline(deadline)
+			.send()
+			.map_err(|_| "Adapter unreachable")?;
+		let response = pending.try_wait(deadline)
+			.map_err(|_| "Adapter timeout")?
+			.map_err(|_| "Adapter timeout")?;
+		if response.code != 200 {
+			return Err("Adapter error");
+		}
+		Ok(response.body().collect())
+	}
+}
+
+// The parameters of a request, which must have been built with `RequestParameters`
+fn decode_parameters(data_version: DataVersion, data: &[u8]) -> Result<RequestParameters, &'static str> {
+	RequestParameters::from_data(data_version, data).map_err(|_| "Invalid request parameters")
+}
+
+// The Chainlink adapter request for a request: `{"id": "<request id>", "data": {<parameters>}}`
+fn request_body(request_id: RequestIdentifier, parameters: &RequestParameters) -> Vec<u8> {
+	let mut body = b"{\"id\":".to_vec();
+	push_json_string(&mut body, &encoding::decimal(request_id.into()));
+	body.extend_from_slice(b",\"data\":{");
+	for (index, (key, value)) in parameters.0.iter().enumerate() {
+		if index > 0 {
+			body.push(b',');
+		}
+		push_json_string(&mut body, key);
+		body.push(b':');
+		push_json_string(&mut body, value);
+	}
+	body.extend_from_slice(b"}}");
+	body
+}
+
+fn push_json_string(json: &mut Vec<u8>, value: &[u8]) {
+	json.push(b'"');
+	for byte in value {
+		match byte {
+			b'"' => json.extend_from_slice(b"\\\""),
+			b'\\' => json.extend_from_slice(b"\\\\"),
+			0..=0x1f => json.extend_from_slice(&[b'\\', b'u', b'0', b'0', hex_digit(byte >> 4), hex_digit(byte & 0xf)]),
+			_ => json.push(*byte),
+		}
+	}
+	json.push(b'"');
+}
+
+fn hex_digit(value: u8) -> u8 {
+	match value {
+		0..=9 => b'0' + value,
+		_ => b'a' + value - 10,
+	}
+}
+
+// Extract the `result` of an adapter response, SCALE encoded as the `resultType` parameter requires (`int` by default)
+fn encode_result(parameters: &RequestParameters, response: &[u8]) -> Result<Vec<u8>, &'static str> {
+	let response = core::str::from_utf8(response).map_err(|_| "Invalid adapter response")?;
+	let response = lite_json::parse_json(response).map_err(|_| "Invalid adapter response")?;
+	let result = field(&response, "result")
+		.or_else(|| field(&response, "data").and_then(|data| field(data, "result")))
+		.ok_or("No result in adapter response")?;
+
+	let result_type = parameters.value(encoding::RESULT_TYPE).unwrap_or(<i128 as encoding::OracleResult>::RESULT_TYPE);
+	match (result_type, result) {
+		(b"int", JsonValue::Number(number)) if number.fraction == 0 && number.exponent == 0 => Ok((number.integer as i128).encode()),
+		(b"int", JsonValue::String(text)) => parse::<i128>(text).map(|value| value.encode()),
+		(b"uint", JsonValue::Number(number)) if number.fraction == 0 && number.exponent == 0 && number.integer >= 0 => Ok((number.integer as u128).encode()),
+		(b"uint", JsonValue::String(text)) => parse::<u128>(text).map(|value| value.encode()),
+		(b"bool", JsonValue::Boolean(value)) => Ok(value.encode()),
+		(b"string", JsonValue::String(text)) => Ok(utf8(text).encode()),
+		(b"bytes32", JsonValue::String(text)) => parse_bytes32(text).map(|value| value.encode()),
+		(b"price", JsonValue::Number(number)) if number.fraction == 0 && number.exponent == 0 => Ok((number.integer as i128, decimals(&response)?).encode()),
+		(b"price", JsonValue::String(text)) => Ok((parse::<i128>(text)?, decimals(&response)?).encode()),
+		_ => Err("Unexpected result type"),
+	}
+}
+
+// The `decimals` field of an adapter response, found next to its `result`
+fn decimals(response: &JsonValue) -> Result<u8, &'static str> {
+	let decimals = field(response, "decimals").or_else(|| field(response, "data").and_then(|data| field(data, "decimals")));
+	match decimals {
+		Some(JsonValue::Number(number)) if number.fraction == 0 && number.exponent == 0 && number.integer >= 0 && number.integer <= u8::max_value() as i64 => Ok(number.integer as u8),
+		Some(JsonValue::String(text)) => parse::<u8>(text),
+		_ => Err("No decimals in adapter response"),
+	}
+}
+
+fn field<'a>(object: &'a JsonValue, name: &str) -> Option<&'a JsonValue> {
+	match object {
+		JsonValue::Object(fields) => fields.iter()
+			.find(|(key, _)| key.iter().copied().eq(name.chars()))
+			.map(|(_, value)| value),
+		_ => None,
+	}
+}
+
+fn utf8(text: &[char]) -> Vec<u8> {
+	let mut bytes = Vec::with_capacity(text.len());
+	for character in text {
+		bytes.extend_from_slice(character.encode_utf8(&mut [0; 4]).as_bytes());
+	}
+	bytes
+}
+
+fn parse<N: core::str::FromStr>(text: &[char]) -> Result<N, &'static str> {
+	let text = utf8(text);
+	core::str::from_utf8(&text).ok().and_then(|text| text.parse().ok()).ok_or("Invalid number")
+}
+
+// A `0x` prefixed hexadecimal string of 32 bytes
+fn parse_bytes32(text: &[char]) -> Result<[u8; 32], &'static str> {
+	let digits = match text {
+		['0', 'x', digits @ ..] => digits,
+		_ => return Err("Invalid bytes32"),
+	};
+	if digits.len() != 64 {
+		return Err("Invalid bytes32");
+	}
+	let mut bytes = [0u8; 32];
+	for (index, pair) in digits.chunks(2).enumerate() {
+		let high = pair[0].to_digit(16).ok_or("Invalid bytes32")?;
+		let low = pair[1].to_digit(16).ok_or("Invalid bytes32")?;
+		bytes[index] = (high * 16 + low) as u8;
+	}
+	Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn requests_are_sent_as_adapter_requests() {
+		let parameters = RequestParameters::new().with("pricePair", "ETH-USD").with("quote", "\"x\"\n");
+
+		assert_eq!(
+			request_body(7, &parameters),
+			b"{\"id\":\"7\",\"data\":{\"pricePair\":\"ETH-USD\",\"quote\":\"\\\"x\\\"\\u000a\"}}".to_vec()
+		);
+		assert!(decode_parameters(2, &parameters.clone().into_data()).is_err());
+		assert_eq!(decode_parameters(RequestParameters::DATA_VERSION, &parameters.clone().into_data()), Ok(parameters.clone()));
+		assert_eq!(decode_parameters(RequestParameters::LEGACY_DATA_VERSION, &parameters.0.encode()), Ok(parameters));
+	}
+
+	#[test]
+	fn adapter_results_are_encoded() {
+		let parameters = |result_type: &str| RequestParameters::new().with(encoding::RESULT_TYPE, result_type);
+
+		assert_eq!(encode_result(&RequestParameters::new(), br#"{"jobRunID":"1","data":{},"result":-42}"#), Ok((-42i128).encode()));
+		assert_eq!(encode_result(&parameters("int"), br#"{"data":{"result":"123456789012"}}"#), Ok(123456789012i128.encode()));
+		assert_eq!(encode_result(&parameters("uint"), br#"{"result":42}"#), Ok(42u128.encode()));
+		assert_eq!(encode_result(&parameters("bool"), br#"{"result":true}"#), Ok(true.encode()));
+		assert_eq!(encode_result(&parameters("string"), br#"{"result":"ETH"}"#), Ok(b"ETH".to_vec().encode()));
+		let bytes32 = format!(r#"{{"result":"0x{}"}}"#, "01".repeat(32));
+		assert_eq!(encode_result(&parameters("bytes32"), bytes32.as_bytes()), Ok([1u8; 32].encode()));
+
+		assert_eq!(encode_result(&parameters("price"), br#"{"data":{"result":"150000000","decimals":8}}"#), Ok((150_000_000i128, 8u8).encode()));
+		assert_eq!(encode_result(&parameters("price"), br#"{"result":-15,"decimals":"1"}"#), Ok((-15i128, 1u8).encode()));
+
+		assert!(encode_result(&parameters("uint"), br#"{"result":-1}"#).is_err());
+		assert!(encode_result(&parameters("price"), br#"{"result":15}"#).is_err());
+		assert!(encode_result(&parameters("price"), br#"{"result":15,"decimals":256}"#).is_err());
+		assert!(encode_result(&parameters("int"), br#"{"result":1.5}"#).is_err());
+		assert!(encode_result(&parameters("int"), br#"{"data":{}}"#).is_err());
+	}
+}
@@ -48,10 +48,11 @@ const createRequest = (input, callback) => {
   }
 
   const priceFeed = getPriceFeed(network, infuraProjectKey, priceFeedContract);
-  priceFeed.methods.latestRoundData().call()
-      .then((roundData) => {
+  Promise.all([priceFeed.methods.latestRoundData().call(), priceFeed.methods.decimals().call()])
+      .then(([roundData, decimals]) => {
         console.log("Latest Round Data", roundData)
         roundData.result = roundData.answer
+        roundData.decimals = decimals
         roundData.pricePair = pricePair
         roundData.network = network
         roundData.priceFeedContract = priceFeedContract
@@ -23,6 +23,7 @@ describe('createRequest', () => {
           assert.isNotEmpty(data.data)
           assert.isAbove(Number(data.result), 0)
           assert.isAbove(Number(data.data.result), 0)
+          assert.isAtLeast(Number(data.data.decimals), 0)
           if (req.testData.data.network) {
             assert.equal(data.data.network, req.testData.data.network)
           }
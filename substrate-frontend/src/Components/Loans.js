@@ -186,8 +186,8 @@ function Main(props) {
 
     useEffect(() => {
         let unsubscribe;
-        api.query.pricefeed.price(newValue => {
-            setKDValue(newValue.toNumber());
+        api.query.pricefeed.prices('LINK/USD', newValue => {
+            setKDValue(newValue.isNone ? 0 : newValue.unwrap().value.toNumber());
         }).then(unsub => {
             unsubscribe = unsub;
         }).catch(console.error);
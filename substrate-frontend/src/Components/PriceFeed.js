@@ -21,8 +21,7 @@ function Main (props) {
 
   // The currently stored value
   const [currentValue, setCurrentValue] = useState(0);
-  const [pricePair, setPricePair] = useState(0);
-  const [latestPricePair, setLatestPricePair] = useState(0);
+  const [pricePair, setPricePair] = useState('ETH/USD');
 
   const  bin2String = (array) => {
         var result = "";
@@ -34,8 +33,9 @@ function Main (props) {
 
   useEffect(() => {
     let unsubscribe;
-    api.query.pricefeed.price(newValue => {
-      // The storage value is an Option<u32>
+    // Prices are stored by price pair
+    api.query.pricefeed.prices(pricePair, newValue => {
+      // The storage value is an Option<PriceData>
       // So we have to check whether it is None first
       // There is also unwrapOr
         console.log(newValue)
@@ -43,7 +43,7 @@ function Main (props) {
         setCurrentValue('<None>');
       } else {
           try{
-              setCurrentValue(Math.round(newValue.toNumber() / 1000000) / 100);
+              setCurrentValue(Math.round(newValue.unwrap().value.toNumber() / 1000000) / 100);
           }catch (e){
               setCurrentValue('NaN');
           }
@@ -52,14 +52,9 @@ function Main (props) {
     }).then(unsub => {
       unsubscribe = unsub;
     }).catch(console.error);
-      api.query.pricefeed.pricePair(pricePair => {
-          setLatestPricePair(pricePair.toHuman());
-      }).then(unsub => {
-          unsubscribe = unsub;
-      }).catch(console.error);
 
     return () => unsubscribe && unsubscribe();
-  }, [api.query.pricefeed]);
+  }, [api.query.pricefeed, pricePair]);
 
   return (
       <Grid.Column width={8}>
@@ -67,7 +62,7 @@ function Main (props) {
         <Card centered>
           <Card.Content textAlign='center'>
             <Statistic
-                label={latestPricePair}
+                label={pricePair}
                 value={currentValue}
             />
           </Card.Content>
@@ -111,6 +106,6 @@ function Main (props) {
 
 export default function PriceFeed (props) {
   const { api } = useSubstrate();
-  return (api.query.pricefeed && api.query.pricefeed.price
+  return (api.query.pricefeed && api.query.pricefeed.prices
       ? <Main {...props} /> : null);
 }
@@ -1,8 +1,11 @@
 use sp_core::{Pair, Public, sr25519};
 use kidot_substrate_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig,
-	SudoConfig, SystemConfig, WASM_BINARY, Signature
+	AccountId, AuraConfig, Balance, BalancesConfig, ChainlinkConfig, GenesisConfig, GrandpaConfig,
+	PricefeedConfig, Runtime, SudoConfig, SystemConfig, WASM_BINARY, Signature
 };
+use kidot_substrate_runtime::chainlink::encoding::RequestParameters;
+use kidot_substrate_runtime::kidot_loan::KD_PRICE_PAIR;
+use kidot_substrate_runtime::pricefeed;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_finality_grandpa::AuthorityId as GrandpaId;
 use sp_runtime::traits::{Verify, IdentifyAccount};
@@ -14,6 +17,13 @@ use sc_service::ChainType;
 /// Specialized `ChainSpec`. This is a specialization of the general Substrate ChainSpec type.
 pub type ChainSpec = sc_service::GenericChainSpec<GenesisConfig>;
 
+/// The JobId of the price feed on the development Chainlink node, to replace by the one of your Chainlink Job
+const DEV_ORACLE_JOB_ID: &[u8] = b"pricefeed";
+/// The minimum fee of the price feed job, covering the price feed `RefreshFee` and `RequestFee`
+const DEV_ORACLE_MIN_FEE: Balance = 100;
+/// The price of one KD$ until the Oracle answers: 1 USD, with 8 decimals
+const DEV_KD_PRICE: i128 = 100_000_000;
+
 /// Generate a crypto pair from seed.
 pub fn get_from_seed<TPublic: Public>(seed: &str) -> <TPublic::Pair as Pair>::Public {
 	TPublic::Pair::from_string(&format!("//{}", seed), None)
@@ -62,6 +72,8 @@ pub fn development_config() -> Result<ChainSpec, String> {
 				get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
 				get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
 			],
+			// Price feed Oracle
+			get_account_id_from_seed::<sr25519::Public>("Alice"),
 			true,
 		),
 		// Bootnodes
@@ -110,6 +122,8 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 				get_account_id_from_seed::<sr25519::Public>("Eve//stash"),
 				get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
 			],
+			// Price feed Oracle
+			get_account_id_from_seed::<sr25519::Public>("Alice"),
 			true,
 		),
 		// Bootnodes
@@ -131,6 +145,7 @@ fn testnet_genesis(
 	initial_authorities: Vec<(AuraId, GrandpaId)>,
 	root_key: AccountId,
 	endowed_accounts: Vec<AccountId>,
+	oracle_account_id: AccountId,
 	_enable_println: bool,
 ) -> GenesisConfig {
 	GenesisConfig {
@@ -141,7 +156,10 @@ fn testnet_genesis(
 		}),
 		pallet_balances: Some(BalancesConfig {
 			// Configure endowed accounts with initial balance of 1 << 60.
-			balances: endowed_accounts.iter().cloned().map(|k|(k, 1 << 60)).collect(),
+			// The pricefeed pallet account too, which pays the price refreshes
+			balances: endowed_accounts.iter().cloned()
+				.chain(Some(pricefeed::Module::<Runtime>::account_id()))
+				.map(|k|(k, 1 << 60)).collect(),
 		}),
 		pallet_aura: Some(AuraConfig {
 			authorities: initial_authorities.iter().map(|x| (x.0.clone())).collect(),
@@ -153,5 +171,20 @@ fn testnet_genesis(
 			// Assign network admin rights.
 			key: root_key,
 		}),
+		chainlink: Some(ChainlinkConfig {
+			// The Oracle is registered and publishes the price feed job, so that refreshes are accepted right away
+			operators: vec![(
+				oracle_account_id.clone(),
+				vec![(DEV_ORACLE_JOB_ID.to_vec(), DEV_ORACLE_MIN_FEE, vec![RequestParameters::DATA_VERSION])],
+			)],
+			..Default::default()
+		}),
+		pricefeed: Some(PricefeedConfig {
+			oracle_account_id: Some(oracle_account_id),
+			oracle_job_id: DEV_ORACLE_JOB_ID.to_vec(),
+			price_pair: KD_PRICE_PAIR.to_vec(),
+			// So that loans can be completed before the Oracle answers
+			price: Some(DEV_KD_PRICE),
+		}),
 	}
 }
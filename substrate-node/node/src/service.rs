@@ -16,6 +16,7 @@ native_executor_instance!(
 	pub Executor,
 	kidot_substrate_runtime::api::dispatch,
 	kidot_substrate_runtime::native_version,
+	frame_benchmarking::benchmarking::HostFunctions,
 );
 
 type FullClient = sc_service::TFullClient<Block, RuntimeApi, Executor>;
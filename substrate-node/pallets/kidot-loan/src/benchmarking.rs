@@ -0,0 +1,100 @@
+//! Benchmarks of the extrinsics of the Kidot loan pallet
+//!
+//! Loans are only completed when the price feed allows it: the runtime sets the price used by the benchmarks
+//! by implementing this module `Trait`, and benchmarks this pallet through this module `Module`.
+
+use super::*;
+use frame_benchmarking::{account, benchmarks};
+use frame_system::RawOrigin;
+use sp_runtime::traits::Bounded;
+
+const SEED: u32 = 0;
+/// Price of one KD, in USD with 8 decimals
+const PRICE: i128 = 100_000_000;
+/// Lent by each lender, so that a loan of `n` USD is completed by its `n`-th lender
+const LEND_AMOUNT: Amount = 1_000;
+
+pub trait Trait: crate::Trait {
+    /// Set the price of one KD$ returned by `Trait::PriceFeed`
+    fn set_latest_price(price: i128);
+}
+
+pub struct Module<T: Trait>(crate::Module<T>);
+
+fn lender<T: Trait>(index: u32) -> T::AccountId {
+    let lender: T::AccountId = account("lender", index, SEED);
+    T::Currency::make_free_balance_be(&lender, BalanceOf::<T>::max_value() / 1_000_000u32.into());
+    lender
+}
+
+/// Add `l` loans, each one lent by `n` lenders, all but the last one completing it
+fn loans<T: Trait>(l: u32, n: u32, completed: bool) -> Result<(), &'static str> {
+    T::set_latest_price(PRICE);
+    for loan in 0..l {
+        crate::Module::<T>::add_loan(RawOrigin::Root.into(), loan, n)?;
+        let lenders = if completed || loan + 1 < l { n } else { n - 1 };
+        for index in 0..lenders {
+            crate::Module::<T>::lend(RawOrigin::Signed(lender::<T>(index)).into(), loan, LEND_AMOUNT)?;
+        }
+    }
+    Ok(())
+}
+
+benchmarks! {
+    _ { }
+
+    reset_loans {
+        let l in 1 .. T::MaxLoans::get();
+        let n in 1 .. T::MaxLendersPerLoan::get();
+        loans::<T>(l, n, true)?;
+    }: _(RawOrigin::Root)
+    verify {
+        assert!(crate::Module::<T>::get_loans().is_empty());
+    }
+
+    add_loan {
+        let l in 0 .. T::MaxLoans::get().saturating_sub(1);
+        loans::<T>(l, 0, true)?;
+    }: _(RawOrigin::Root, l, 1)
+    verify {
+        assert_eq!(crate::Module::<T>::get_loans().len(), l as usize + 1);
+    }
+
+    // The last lender completes the loan
+    lend {
+        let l in 1 .. T::MaxLoans::get();
+        let n in 1 .. T::MaxLendersPerLoan::get();
+        loans::<T>(l, n, false)?;
+        let last_lender = lender::<T>(n - 1);
+    }: _(RawOrigin::Signed(last_lender), l - 1, LEND_AMOUNT)
+    verify {
+        assert_eq!(crate::Module::<T>::get_funded_loans_amount(), l * n * LEND_AMOUNT);
+    }
+
+    payback {
+        let l in 1 .. T::MaxLoans::get();
+        let n in 1 .. T::MaxLendersPerLoan::get();
+        loans::<T>(l, n, true)?;
+        let caller = lender::<T>(0);
+    }: _(RawOrigin::Signed(caller))
+    verify {
+        assert_eq!(crate::Module::<T>::get_payed_back_loans_amount(), 2 * l * n * LEND_AMOUNT / 10);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{new_test_ext, Test};
+    use frame_support::assert_ok;
+
+    #[test]
+    fn benchmarks_run() {
+        new_test_ext().execute_with(|| {
+            assert_ok!(test_benchmark_reset_loans::<Test>());
+            assert_ok!(test_benchmark_add_loan::<Test>());
+            assert_ok!(test_benchmark_lend::<Test>());
+            assert_ok!(test_benchmark_payback::<Test>());
+        });
+    }
+}
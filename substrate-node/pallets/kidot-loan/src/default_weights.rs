@@ -0,0 +1,44 @@
+//! Default weights of the extrinsics of the Kidot loan pallet, used with `type WeightInfo = ()`
+//!
+//! Database accesses are counted from the worst case of each extrinsic, where every loan is completed.
+//! Execution times should be refreshed from the `kidot-loan` benchmarks whenever the extrinsics change.
+
+use frame_support::weights::{Weight, constants::RocksDbWeight as DbWeight};
+
+impl crate::WeightInfo for () {
+    fn reset_loans(l: u32, n: u32, ) -> Weight {
+        (45_000_000 as Weight)
+            .saturating_add((9_000_000 as Weight).saturating_mul(l as Weight))
+            .saturating_add((600_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(DbWeight::get().reads(1 as Weight))
+            .saturating_add(DbWeight::get().reads((2 as Weight).saturating_mul(l as Weight)))
+            .saturating_add(DbWeight::get().writes(6 as Weight))
+            .saturating_add(DbWeight::get().writes((2 as Weight).saturating_mul(l as Weight)))
+    }
+    fn add_loan(l: u32, ) -> Weight {
+        (28_000_000 as Weight)
+            .saturating_add((150_000 as Weight).saturating_mul(l as Weight))
+            .saturating_add(DbWeight::get().reads(1 as Weight))
+            .saturating_add(DbWeight::get().writes(3 as Weight))
+    }
+    fn lend(l: u32, n: u32, ) -> Weight {
+        (96_000_000 as Weight)
+            .saturating_add((200_000 as Weight).saturating_mul(l as Weight))
+            .saturating_add((48_000_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(DbWeight::get().reads(9 as Weight))
+            .saturating_add(DbWeight::get().reads((1 as Weight).saturating_mul(n as Weight)))
+            .saturating_add(DbWeight::get().writes(9 as Weight))
+            .saturating_add(DbWeight::get().writes((1 as Weight).saturating_mul(n as Weight)))
+    }
+    fn payback(l: u32, n: u32, ) -> Weight {
+        (38_000_000 as Weight)
+            .saturating_add((52_000_000 as Weight).saturating_mul(l as Weight))
+            .saturating_add((95_000_000 as Weight).saturating_mul((l as Weight).saturating_mul(n as Weight)))
+            .saturating_add(DbWeight::get().reads(4 as Weight))
+            .saturating_add(DbWeight::get().reads((6 as Weight).saturating_mul(l as Weight)))
+            .saturating_add(DbWeight::get().reads((2 as Weight).saturating_mul((l as Weight).saturating_mul(n as Weight))))
+            .saturating_add(DbWeight::get().writes(3 as Weight))
+            .saturating_add(DbWeight::get().writes((5 as Weight).saturating_mul(l as Weight)))
+            .saturating_add(DbWeight::get().writes((2 as Weight).saturating_mul((l as Weight).saturating_mul(n as Weight))))
+    }
+}
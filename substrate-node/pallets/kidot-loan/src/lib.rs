@@ -2,11 +2,11 @@
 
 use codec::{Decode, Encode};
 use frame_support::{decl_error, decl_event, decl_module, decl_storage, dispatch, ensure,
-                    traits::{Currency}};
+                    traits::{Currency, Get}, weights::Weight};
 use frame_support::traits::{BalanceStatus, ReservableCurrency, ExistenceRequirement};
 use frame_system::{self as system, ensure_root, ensure_signed};
 use log::info;
-use sp_runtime::{ModuleId, traits::AccountIdConversion};
+use sp_runtime::{FixedPointNumber, FixedU128, ModuleId, traits::AccountIdConversion};
 use sp_std::prelude::*;
 use pricefeed::PriceFeeds;
 
@@ -16,6 +16,10 @@ mod mock;
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "runtime-benchmarks")]
+pub mod benchmarking;
+mod default_weights;
+
 pub type LoanId = u32;
 pub type Amount = u32;
 
@@ -39,10 +43,38 @@ type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::Ac
 /// Must be exactly 8 characters long
 const KIDOT_ACCOUNT_ID: ModuleId = ModuleId(*b".Ki.Dot.");
 
+/// The price feed pair giving the value of one KD$ in USD
+pub const KD_PRICE_PAIR: &[u8] = b"LINK/USD";
+
+/// Base units of `Amount` in one KD$
+pub const UNITS_PER_KD: u128 = 1_000;
+
+/// Cents in one USD
+pub const CENTS_PER_USD: u128 = 100;
+
 pub trait Trait: frame_system::Trait {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
     type Currency: ReservableCurrency<Self::AccountId>;
-    type PriceFeed: PriceFeeds;
+    type PriceFeed: PriceFeeds<BlockNumber = Self::BlockNumber>;
+    /// Maximum age, in blocks, of the KD$ price used to fund and pay back loans
+    type MaxPriceAge: Get<Self::BlockNumber>;
+    /// Window, in blocks, of the KD$ time-weighted average price used to complete loans
+    type PriceWindow: Get<Self::BlockNumber>;
+    /// Maximum number of loans, bounding the weight of `payback`
+    type MaxLoans: Get<u32>;
+    /// Maximum number of lenders of a loan, bounding the weight of `lend` and `payback`
+    type MaxLendersPerLoan: Get<u32>;
+    /// Weights of the extrinsics, `()` provides default ones
+    type WeightInfo: WeightInfo;
+}
+
+/// Weights of the extrinsics of this pallet
+/// `l` is the number of loans, `n` the number of lenders of each loan
+pub trait WeightInfo {
+    fn reset_loans(l: u32, n: u32) -> Weight;
+    fn add_loan(l: u32) -> Weight;
+    fn lend(l: u32, n: u32) -> Weight;
+    fn payback(l: u32, n: u32) -> Weight;
 }
 
 decl_storage! {
@@ -87,6 +119,12 @@ decl_error! {
 		LoanAlreadyCompleted,
 		/// The lender has not enough bucks to fund.
 		InsufficientBalance,
+		/// There are already `MaxLoans` loans.
+		TooManyLoans,
+		/// The loan already has `MaxLendersPerLoan` lenders.
+		TooManyLenders,
+		/// The KD$ price is missing, older than `MaxPriceAge` or not a valid price.
+		PriceUnavailable,
 	}
 }
 
@@ -98,8 +136,13 @@ decl_module! {
 		// Events must be initialized if they are used by the pallet.
 		fn deposit_event() = default;
 
+		const MaxLoans: u32 = T::MaxLoans::get();
+		const MaxLendersPerLoan: u32 = T::MaxLendersPerLoan::get();
+		const MaxPriceAge: T::BlockNumber = T::MaxPriceAge::get();
+		const PriceWindow: T::BlockNumber = T::PriceWindow::get();
+
 		/// Reset loans
-		#[weight = 0]
+		#[weight = T::WeightInfo::reset_loans(T::MaxLoans::get(), T::MaxLendersPerLoan::get())]
 		pub fn reset_loans(origin) -> dispatch::DispatchResult {
 			// Checks
 			ensure_root(origin)?;
@@ -117,11 +160,12 @@ decl_module! {
 		}
 
 		/// Add a new loan
-		#[weight = 0]
+		#[weight = T::WeightInfo::add_loan(T::MaxLoans::get())]
 		pub fn add_loan(origin, loan_id: LoanId, loan_amount: Amount) -> dispatch::DispatchResult {
 			// Checks
 			ensure_root(origin)?;
 			ensure!(!Self::get_loans().contains(&loan_id), Error::<T>::LoanAlreadyExists);
+			ensure!((Self::get_loans().len() as u32) < T::MaxLoans::get(), Error::<T>::TooManyLoans);
 
 			Self::create_loan(loan_id, loan_amount);
 
@@ -130,14 +174,16 @@ decl_module! {
 		}
 
 		/// Lend some bucks to a loan
-		#[weight = 0]
+		#[weight = T::WeightInfo::lend(T::MaxLoans::get(), T::MaxLendersPerLoan::get())]
 		pub fn lend(origin, loan: LoanId, amount: Amount) -> dispatch::DispatchResult {
 			// Checks
 			let who = ensure_signed(origin)?;
 			ensure!(T::Currency::can_reserve(&who, amount.into()), Error::<T>::InsufficientBalance);
-			ensure!(!Self::loan_is_completed(loan), Error::<T>::LoanAlreadyCompleted);
+			let price = Self::kd_price()?;
+			ensure!(!Self::loan_is_completed(loan, price), Error::<T>::LoanAlreadyCompleted);
 			let loans = Self::get_loans();
 			ensure!(loans.contains(&loan), Error::<T>::LoanAlreadyExists);
+			ensure!((Self::get_loan_lenders(loan).len() as u32) < T::MaxLendersPerLoan::get(), Error::<T>::TooManyLenders);
 
 			T::Currency::reserve(&who, Self::amount_to_reserve(amount).into())?;
 			Self::add_lender(loan, who.clone(), amount);
@@ -146,16 +192,18 @@ decl_module! {
 			info!("There's now {} loans", Self::get_loans().len());
 
 			Self::deposit_event(RawEvent::LoanFunded(loan, who, amount));
-			Self::fund_loan_if_enough_amount(loan);
+			Self::fund_loan_if_enough_amount(loan, price);
 			Ok(())
 		}
 
 		/// Simulate a one month payback for all completed loans
-		#[weight = 0]
+		/// Loans already being paid back keep being paid back while the KD$ price is unavailable.
+		#[weight = T::WeightInfo::payback(T::MaxLoans::get(), T::MaxLendersPerLoan::get())]
 		pub fn payback(origin) -> dispatch::DispatchResult {
 			// Checks
 			let _who = ensure_signed(origin)?;
-			Self::payback_completed_loans();
+			let price = Self::kd_price().ok();
+			Self::payback_completed_loans(price);
 			Ok(())
 		}
 	}
@@ -230,7 +278,7 @@ impl<T: Trait> Module<T> {
         <Loans>::put(loans);
     }
 
-    fn fund_loan_if_enough_amount(loan: LoanId) {
+    fn fund_loan_if_enough_amount(loan: LoanId, price: FixedU128) {
         let lenders = Self::get_loan_lenders(loan);
         let mut funded_amount: u32 = 0;
         for i in 0..lenders.len() {
@@ -238,7 +286,7 @@ impl<T: Trait> Module<T> {
         }
         info!("Amount funded for {} = {}", loan, funded_amount);
 
-        if Self::loan_is_completed(loan) {
+        if Self::loan_is_completed(loan, price) {
             Self::deposit_event(RawEvent::LoanFullyFunded(loan, funded_amount));
             for i in 0..lenders.len() {
                 let _ = T::Currency::repatriate_reserved(&lenders[i].lender_account, &Self::account_id(),
@@ -252,13 +300,14 @@ impl<T: Trait> Module<T> {
         }
     }
 
-    fn payback_completed_loans() {
+    /// Loans are completed at `price`, unless their payback already started: those don't need a price.
+    fn payback_completed_loans(price: Option<FixedU128>) {
         // New reward from staking, give it to the Ki.Dot pot
         let monthly_reward_from_staking : Amount = Self::get_staked_amount() / 100;  // 1% per month
         let _ = T::Currency::deposit_into_existing(&Self::account_id(), monthly_reward_from_staking.into());
         for loan_id in &Self::get_loans() {
-            if Self::loan_is_completed(loan_id.clone()){
-                let mut loan_details = Self::get_loan_details(loan_id);
+            let mut loan_details = Self::get_loan_details(loan_id);
+            if loan_details.payed_back_amount > 0 || price.map_or(false, |price| Self::loan_is_completed(*loan_id, price)) {
                 if loan_details.payed_back_amount < loan_details.funded_amount{
                     let amount_paid_back: Amount = loan_details.funded_amount / 10; // Paid back in 10 months
                     info!("Paying back {} to {}, need {} to paid back", amount_paid_back, loan_id, loan_details.funded_amount - loan_details.payed_back_amount);
@@ -296,12 +345,29 @@ impl<T: Trait> Module<T> {
         }
     }
 
-    fn loan_is_completed(loan: LoanId) -> bool {
+    /// The time-weighted average price of one KD$ in USD over `PriceWindow`, so that a single spiky price can't complete a loan
+    /// Fails if the latest price is missing, older than `MaxPriceAge`, negative or waiting on governance after a quarantined answer.
+    fn kd_price() -> Result<FixedU128, Error<T>> {
+        let latest_price = T::PriceFeed::fixed_price_checked(KD_PRICE_PAIR, T::MaxPriceAge::get())
+            .map_err(|_| Error::<T>::PriceUnavailable)?;
+        Ok(T::PriceFeed::twap(KD_PRICE_PAIR, T::PriceWindow::get()).unwrap_or(latest_price).value)
+    }
+
+    /// The value in USD cents of `amount` KD$ base units at `price` USD per KD$, rounded down
+    /// `None` if the value overflows.
+    pub fn usd_cents(amount: Amount, price: FixedU128) -> Option<u128> {
+        let cents_per_kd = price.checked_mul_int(u128::from(amount).checked_mul(CENTS_PER_USD)?)?;
+        Some(cents_per_kd / UNITS_PER_KD)
+    }
+
+    /// Whether the funded amount of `loan` is worth its whole amount at `price`
+    /// The funded value is rounded down, and a value overflowing never completes a loan.
+    fn loan_is_completed(loan: LoanId, price: FixedU128) -> bool {
         let loan_details = Self::get_loan_details(loan);
-        // One KD = 1000 unit
-        let funded_in_usd = (loan_details.funded_amount / 1000) * (T::PriceFeed::latest_price() as u32 / 100000000);
-        info!("Amount funded for {} = {} mKD$ = {} USD / {}", loan, loan_details.funded_amount, funded_in_usd, loan_details.loan_amount);
-        return loan_details.loan_amount > 0 && funded_in_usd >= loan_details.loan_amount;
+        let funded_in_cents = Self::usd_cents(loan_details.funded_amount, price);
+        let loan_in_cents = u128::from(loan_details.loan_amount).saturating_mul(CENTS_PER_USD);
+        info!("Amount funded for {} = {} mKD$ = {:?} USD cents / {}", loan, loan_details.funded_amount, funded_in_cents, loan_in_cents);
+        loan_details.loan_amount > 0 && funded_in_cents.map_or(false, |funded_in_cents| funded_in_cents >= loan_in_cents)
     }
 
 
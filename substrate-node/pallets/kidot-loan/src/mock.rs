@@ -5,6 +5,8 @@ use sp_runtime::{
 	traits::{BlakeTwo256, IdentityLookup}, testing::Header, Perbill,
 };
 use frame_system as system;
+use pricefeed::{FixedPrice, PriceData, PriceError, PriceFeeds};
+use std::cell::RefCell;
 
 impl_outer_origin! {
 	pub enum Origin for Test {}
@@ -65,9 +67,75 @@ impl balances::Trait for Test {
 }
 
 
+parameter_types! {
+	pub const MaxLoans: u32 = 3;
+	pub const MaxLendersPerLoan: u32 = 3;
+	pub const MaxPriceAge: u64 = 10;
+	pub const PriceWindow: u64 = 5;
+}
+
+/// Decimals of the KD$ prices
+pub const PRICE_DECIMALS: u8 = 8;
+
+thread_local! {
+	static PRICE: RefCell<Option<PriceData<u64, u64>>> = RefCell::new(None);
+	static TWAP: RefCell<Option<i128>> = RefCell::new(None);
+}
+
+/// Receive the KD$ price in the current block, with `PRICE_DECIMALS` decimals
+pub fn set_latest_price(value: i128) {
+	let price = PriceData { value, block_number: System::block_number(), decimals: PRICE_DECIMALS, ..Default::default() };
+	PRICE.with(|latest_price| *latest_price.borrow_mut() = Some(price));
+}
+
+/// Average the KD$ price to `value`, with `PRICE_DECIMALS` decimals, instead of its latest value
+pub fn set_twap(value: i128) {
+	TWAP.with(|twap| *twap.borrow_mut() = Some(value));
+}
+
+pub struct TestPriceFeed;
+impl PriceFeeds for TestPriceFeed {
+	type BlockNumber = u64;
+	type Moment = u64;
+
+	fn latest_price(_pair: &[u8]) -> i128 {
+		PRICE.with(|price| price.borrow().as_ref().map_or(0, |price| price.value))
+	}
+
+	fn latest_price_checked(_pair: &[u8], max_age: u64) -> Result<PriceData<u64, u64>, PriceError> {
+		let price = PRICE.with(|price| price.borrow().clone()).ok_or(PriceError::Missing)?;
+		if System::block_number() - price.block_number > max_age {
+			return Err(PriceError::Stale);
+		}
+		Ok(price)
+	}
+
+	fn twap(pair: &[u8], _window: u64) -> Option<FixedPrice> {
+		let value = TWAP.with(|twap| *twap.borrow()).unwrap_or_else(|| Self::latest_price(pair));
+		FixedPrice::from_raw(value, PRICE_DECIMALS)
+	}
+
+	fn median_price(pair: &[u8], _window: u64) -> Option<FixedPrice> {
+		FixedPrice::from_raw(Self::latest_price(pair), PRICE_DECIMALS)
+	}
+}
+
 impl Trait for Test {
 	type Event = ();
 	type Currency = balances::Module<Test>;
+	type PriceFeed = TestPriceFeed;
+	type MaxLoans = MaxLoans;
+	type MaxLendersPerLoan = MaxLendersPerLoan;
+	type MaxPriceAge = MaxPriceAge;
+	type PriceWindow = PriceWindow;
+	type WeightInfo = ();
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl crate::benchmarking::Trait for Test {
+	fn set_latest_price(price: i128) {
+		set_latest_price(price);
+	}
 }
 
 pub type System = system::Module<Test>;
@@ -1,9 +1,13 @@
-use crate::{mock::*};
-use frame_support::{assert_ok};
+use crate::{Error, mock::*};
+use sp_runtime::{FixedPointNumber, FixedU128};
+use frame_support::{assert_noop, assert_ok};
 
 #[test]
-fn it_works_for_default_value() {
+fn loans_are_funded_by_lenders() {
 	new_test_ext().execute_with(|| {
+		assert_ok!(KidotLoanModule::add_loan(Origin::root(), 124589, 50000));
+		set_latest_price(100_000_000);
+
 		// Dispatch a signed extrinsic.
 		assert_ok!(KidotLoanModule::lend(Origin::signed(1), 124589, 500));
 		// Read pallet storage and assert an expected result.
@@ -19,6 +23,80 @@ fn it_works_for_default_value() {
 	});
 }
 
+#[test]
+fn loans_are_not_funded_on_missing_or_stale_prices() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KidotLoanModule::add_loan(Origin::root(), 124589, 1));
+		assert_noop!(KidotLoanModule::lend(Origin::signed(1), 124589, 500), Error::<Test>::PriceUnavailable);
+
+		set_latest_price(100_000_000);
+		System::set_block_number(1 + MaxPriceAge::get() + 1);
+		assert_noop!(KidotLoanModule::lend(Origin::signed(1), 124589, 500), Error::<Test>::PriceUnavailable);
+
+		set_latest_price(100_000_000);
+		assert_ok!(KidotLoanModule::lend(Origin::signed(1), 124589, 500));
+		assert_eq!(KidotLoanModule::get_loan_details(124589).funded_amount, 500);
+	});
+}
+
+#[test]
+fn loans_being_paid_back_dont_need_a_price() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KidotLoanModule::add_loan(Origin::root(), 124589, 1));
+		assert_ok!(KidotLoanModule::add_loan(Origin::root(), 124590, 1));
+		set_latest_price(100_000_000);
+		assert_ok!(KidotLoanModule::lend(Origin::signed(1), 124589, 1000));
+		assert_ok!(KidotLoanModule::payback(Origin::signed(1)));
+		assert_eq!(KidotLoanModule::get_loan_details(124589).payed_back_amount, 100);
+
+		// Only the loan already being paid back is paid back on a stale price
+		assert_ok!(KidotLoanModule::lend(Origin::signed(2), 124590, 1000));
+		System::set_block_number(1 + MaxPriceAge::get() + 1);
+		assert_ok!(KidotLoanModule::payback(Origin::signed(1)));
+		assert_eq!(KidotLoanModule::get_loan_details(124589).payed_back_amount, 200);
+		assert_eq!(KidotLoanModule::get_loan_details(124590).payed_back_amount, 0);
+	});
+}
+
+#[test]
+fn loans_are_completed_on_the_average_price() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KidotLoanModule::add_loan(Origin::root(), 124589, 3));
+
+		// A spike to 3 USD doesn't complete the loan while the average price is 1 USD
+		set_latest_price(300_000_000);
+		set_twap(100_000_000);
+		assert_ok!(KidotLoanModule::lend(Origin::signed(1), 124589, 1000));
+		assert_eq!(KidotLoanModule::get_funded_loans_amount(), 0);
+
+		set_twap(200_000_000);
+		assert_ok!(KidotLoanModule::lend(Origin::signed(2), 124589, 1000));
+		assert_eq!(KidotLoanModule::get_funded_loans_amount(), 2000);
+	});
+}
+
+#[test]
+fn loans_are_completed_on_prices_below_one_usd() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KidotLoanModule::add_loan(Origin::root(), 124589, 1));
+
+		// 1.999 KD$ at 0.5 USD is worth 99.95 cents, rounded down
+		set_latest_price(50_000_000);
+		assert_ok!(KidotLoanModule::lend(Origin::signed(1), 124589, 1999));
+		assert_eq!(KidotLoanModule::get_funded_loans_amount(), 0);
+
+		assert_ok!(KidotLoanModule::lend(Origin::signed(2), 124589, 1));
+		assert_eq!(KidotLoanModule::get_funded_loans_amount(), 2000);
+	});
+}
+
+#[test]
+fn amounts_are_converted_to_usd_cents() {
+	assert_eq!(KidotLoanModule::usd_cents(1_000, FixedU128::saturating_from_rational(1, 2)), Some(50));
+	assert_eq!(KidotLoanModule::usd_cents(1, FixedU128::saturating_from_rational(1, 2)), Some(0));
+	assert_eq!(KidotLoanModule::usd_cents(1_234, FixedU128::saturating_from_integer(10u32)), Some(1_234));
+}
+
 // #[test]
 // fn correct_error_for_none_value() {
 // 	new_test_ext().execute_with(|| {
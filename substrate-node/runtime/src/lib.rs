@@ -7,6 +7,10 @@
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
 use sp_std::prelude::*;
+#[cfg(feature = "offchain-operator")]
+use codec::Encode;
+#[cfg(feature = "offchain-operator")]
+use sp_runtime::SaturatedConversion;
 use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::{
 	ApplyExtrinsicResult, generic, create_runtime_str, impl_opaque_keys, MultiSignature,
@@ -42,6 +46,8 @@ pub use pricefeed::Call as PriceFeedCall;
 
 pub use pricefeed;
 pub use chainlink;
+#[cfg(feature = "offchain-operator")]
+pub use chainlink::offchain as chainlink_operator;
 pub use kidot_loan;
 
 /// An index to a block.
@@ -263,46 +269,188 @@ impl pallet_sudo::Trait for Runtime {
 impl pricefeed::Trait for Runtime {
 	type Event = Event;
 	type Callback = PriceFeedCall<Runtime>;
+	type DefaultOracleJobId = DefaultOracleJobId;
+	type DefaultOracleAccountId = DefaultOracleAccountId;
+	type RefreshInterval = PriceRefreshInterval;
+	type RefreshFee = PriceRefreshFee;
+	type RequestFee = PriceRequestFee;
+	type MaxPricePairs = MaxPricePairs;
+	type PriceDecimals = PriceDecimals;
+	type PriceHistoryDepth = PriceHistoryDepth;
+	type MinPrice = MinPrice;
+	type MaxPrice = MaxPrice;
+	type MaxPriceDeviation = MaxPriceDeviation;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	// No default Oracle: it is set in the genesis config or with `set_oracle_configuration`
+	pub DefaultOracleJobId: Vec<u8> = Vec::new();
+	pub DefaultOracleAccountId: Option<AccountId> = None;
+	pub const PriceRefreshInterval: BlockNumber = 10 * MINUTES;
+	pub const PriceRefreshFee: Balance = 100;
+	pub const PriceRequestFee: Balance = 100;
+	pub const MaxPricePairs: u32 = 20;
+	pub const PriceDecimals: u8 = 8;
+	pub const PriceHistoryDepth: u32 = 32;
+	// Prices of at most 10^12 USD, with 8 decimals
+	pub const MinPrice: i128 = 1;
+	pub const MaxPrice: i128 = 100_000_000_000_000_000_000;
+	pub const MaxPriceDeviation: Perbill = Perbill::from_percent(50);
 }
 
 impl chainlink::Trait for Runtime {
 	type Event = Event;
 	type Currency = Balances;
-	type Callback = PriceFeedCall<Runtime>;
+	type Callback = Call;
 	type ValidityPeriod = ValidityPeriod;
+	type MaxValidityPeriod = MaxValidityPeriod;
+	type OperatorBond = OperatorBond;
+	type UnbondingPeriod = UnbondingPeriod;
+	type MissedRequestSlash = MissedRequestSlash;
+	type SlashOrigin = frame_system::EnsureRoot<AccountId>;
+	type Slash = ();
+	type HistoryDepth = RequestHistoryDepth;
+	type CallbackFailurePolicy = CallbackFailurePolicy;
+	type CancellationGracePeriod = CancellationGracePeriod;
+	type RegistrationOrigin = frame_system::EnsureRoot<AccountId>;
+	type MaxPendingRequestsPerAccount = MaxPendingRequestsPerAccount;
+	type MaxPendingRequests = MaxPendingRequests;
+	type RequestDeposit = RequestDeposit;
+	type MaxCallbackWeight = MaxCallbackWeight;
+	type WeightInfo = ();
+}
+
+// Pallets whose Calls can receive Chainlink results
+chainlink::impl_callback_with_parameter!(Call { Pricefeed });
+
+// Answers requests assigned to Operators whose key is in the node keystore
+#[cfg(feature = "offchain-operator")]
+impl chainlink_operator::Trait for Runtime {
+	type AuthorityId = chainlink_operator::crypto::OperatorId;
+	type RuntimeEvent = Event;
+	type AdapterUrl = AdapterUrl;
+}
+
+#[cfg(feature = "offchain-operator")]
+parameter_types! {
+	pub const AdapterUrl: &'static str = "http://localhost:8080";
+}
+
+#[cfg(feature = "offchain-operator")]
+impl frame_system::offchain::SigningTypes for Runtime {
+	type Public = <Signature as Verify>::Signer;
+	type Signature = Signature;
+}
+
+#[cfg(feature = "offchain-operator")]
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Runtime where
+	Call: From<C>,
+{
+	type OverarchingCall = Call;
+	type Extrinsic = UncheckedExtrinsic;
+}
+
+#[cfg(feature = "offchain-operator")]
+impl<LocalCall> frame_system::offchain::CreateSignedTransaction<LocalCall> for Runtime where
+	Call: From<LocalCall>,
+{
+	fn create_transaction<C: frame_system::offchain::AppCrypto<Self::Public, Self::Signature>>(
+		call: Call,
+		public: <Signature as Verify>::Signer,
+		account: AccountId,
+		nonce: Index,
+	) -> Option<(Call, <UncheckedExtrinsic as sp_runtime::traits::Extrinsic>::SignaturePayload)> {
+		let period = BlockHashCount::get()
+			.checked_next_power_of_two()
+			.map(|c| c / 2)
+			.unwrap_or(2) as u64;
+		let current_block = System::block_number()
+			.saturated_into::<u64>()
+			// The `System::block_number` is initialized with `n+1`,
+			// so the actual block number is `n`.
+			.saturating_sub(1);
+		let extra: SignedExtra = (
+			frame_system::CheckSpecVersion::<Runtime>::new(),
+			frame_system::CheckTxVersion::<Runtime>::new(),
+			frame_system::CheckGenesis::<Runtime>::new(),
+			frame_system::CheckEra::<Runtime>::from(generic::Era::mortal(period, current_block)),
+			frame_system::CheckNonce::<Runtime>::from(nonce),
+			frame_system::CheckWeight::<Runtime>::new(),
+			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(0),
+		);
+		let raw_payload = SignedPayload::new(call, extra).ok()?;
+		let signature = raw_payload.using_encoded(|payload| C::sign(payload, public))?;
+		let (call, extra, _) = raw_payload.deconstruct();
+		Some((call, (account, signature, extra)))
+	}
+}
+
+parameter_types! {
+	pub const MaxLoans: u32 = 50;
+	pub const MaxLendersPerLoan: u32 = 20;
+	pub const MaxPriceAge: BlockNumber = HOURS;
+	pub const PriceWindow: BlockNumber = HOURS;
 }
 
 impl kidot_loan::Trait for Runtime {
 	type Event = Event;
 	type Currency = Balances;
 	type PriceFeed = Pricefeed;
+	type MaxLoans = MaxLoans;
+	type MaxLendersPerLoan = MaxLendersPerLoan;
+	type MaxPriceAge = MaxPriceAge;
+	type PriceWindow = PriceWindow;
+	type WeightInfo = ();
 }
 
 parameter_types! {
 	pub const ValidityPeriod: u32 = 50;
+	pub const MaxValidityPeriod: BlockNumber = HOURS;
+	pub const OperatorBond: Balance = 1_000_000;
+	pub const UnbondingPeriod: BlockNumber = DAYS;
+	pub const MissedRequestSlash: Perbill = Perbill::from_percent(1);
+	pub const RequestHistoryDepth: u32 = 100;
+	pub const CallbackFailurePolicy: chainlink::CallbackFailurePolicy = chainlink::CallbackFailurePolicy::RefundRequester;
+	pub const CancellationGracePeriod: BlockNumber = 10;
+	pub const MaxPendingRequestsPerAccount: u32 = 100;
+	pub const MaxPendingRequests: u32 = 10_000;
+	pub const RequestDeposit: Balance = 1_000;
+	pub const MaxCallbackWeight: Weight = WEIGHT_PER_SECOND / 100;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
-construct_runtime!(
-	pub enum Runtime where
-		Block = Block,
-		NodeBlock = opaque::Block,
-		UncheckedExtrinsic = UncheckedExtrinsic
-	{
-		System: frame_system::{Module, Call, Config, Storage, Event<T>},
-		RandomnessCollectiveFlip: pallet_randomness_collective_flip::{Module, Call, Storage},
-		Timestamp: pallet_timestamp::{Module, Call, Storage, Inherent},
-		Aura: pallet_aura::{Module, Config<T>, Inherent},
-		Grandpa: pallet_grandpa::{Module, Call, Storage, Config, Event},
-		Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
-		TransactionPayment: pallet_transaction_payment::{Module, Storage},
-		Sudo: pallet_sudo::{Module, Call, Config<T>, Storage, Event<T>},
-		// Declare the chainlink pallet
-		Chainlink: chainlink::{Module, Call, Storage, Event<T>},
-		Pricefeed: pricefeed::{Module, Call, Storage},
-		KidotLoan: kidot_loan::{Module, Call, Storage, Event<T>},
+// `construct_runtime!` doesn't support `#[cfg]` on its pallets: the optional ones are appended by the caller.
+macro_rules! runtime {
+	($($optional_pallets:tt)*) => {
+		construct_runtime!(
+			pub enum Runtime where
+				Block = Block,
+				NodeBlock = opaque::Block,
+				UncheckedExtrinsic = UncheckedExtrinsic
+			{
+				System: frame_system::{Module, Call, Config, Storage, Event<T>},
+				RandomnessCollectiveFlip: pallet_randomness_collective_flip::{Module, Call, Storage},
+				Timestamp: pallet_timestamp::{Module, Call, Storage, Inherent},
+				Aura: pallet_aura::{Module, Config<T>, Inherent},
+				Grandpa: pallet_grandpa::{Module, Call, Storage, Config, Event},
+				Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
+				TransactionPayment: pallet_transaction_payment::{Module, Storage},
+				Sudo: pallet_sudo::{Module, Call, Config<T>, Storage, Event<T>},
+				// Declare the chainlink pallet
+				Chainlink: chainlink::{Module, Call, Storage, Config<T>, Event<T>},
+				Pricefeed: pricefeed::{Module, Call, Storage, Event, Config<T>},
+				KidotLoan: kidot_loan::{Module, Call, Storage, Event<T>},
+				$($optional_pallets)*
+			}
+		);
 	}
-);
+}
+
+#[cfg(feature = "offchain-operator")]
+runtime!(ChainlinkOperator: chainlink_operator::{Module},);
+#[cfg(not(feature = "offchain-operator"))]
+runtime!();
 
 /// The address format for describing accounts.
 pub type Address = AccountId;
@@ -326,6 +474,8 @@ pub type SignedExtra = (
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
+/// The payload being signed in transactions.
+pub type SignedPayload = generic::SignedPayload<Call, SignedExtra>;
 /// Extrinsic type that has already been checked.
 pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Call, SignedExtra>;
 /// Executive: handles dispatch to the various modules.
@@ -460,4 +610,72 @@ impl_runtime_apis! {
 			TransactionPayment::query_info(uxt, len)
 		}
 	}
+
+	impl chainlink_runtime_api::ChainlinkApi<Block, AccountId, BlockNumber, Balance> for Runtime {
+		fn request_status(request_id: chainlink::RequestIdentifier) -> Option<chainlink::RequestRecord<AccountId, BlockNumber>> {
+			Chainlink::request_status(request_id)
+		}
+
+		fn requests_of(requester: AccountId) -> Vec<(chainlink::RequestIdentifier, chainlink::RequestRecord<AccountId, BlockNumber>)> {
+			Chainlink::requests_of(requester)
+		}
+
+		fn leaderboard() -> Vec<(AccountId, chainlink::OperatorStats<Balance, BlockNumber>)> {
+			Chainlink::leaderboard()
+		}
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	impl frame_benchmarking::Benchmark<Block> for Runtime {
+		fn dispatch_benchmark(
+			pallet: Vec<u8>,
+			benchmark: Vec<u8>,
+			lowest_range_values: Vec<u32>,
+			highest_range_values: Vec<u32>,
+			steps: Vec<u32>,
+			repeat: u32,
+		) -> Result<Vec<frame_benchmarking::BenchmarkBatch>, sp_runtime::RuntimeString> {
+			use frame_benchmarking::{Benchmarking, BenchmarkBatch, add_benchmark};
+			use frame_support::StorageMap;
+			use chainlink::benchmarking::Module as ChainlinkBench;
+			use kidot_loan::benchmarking::Module as KidotLoanBench;
+
+			// Benchmarked Chainlink requests are answered to the price feed
+			impl chainlink::benchmarking::Trait for Runtime {
+				fn callback() -> Call {
+					PriceFeedCall::callback(vec![], vec![]).into()
+				}
+			}
+
+			impl kidot_loan::benchmarking::Trait for Runtime {
+				fn set_latest_price(price: i128) {
+					let price = pricefeed::PriceData { value: price, block_number: System::block_number(), decimals: PriceDecimals::get(), ..Default::default() };
+					pricefeed::Prices::<Runtime>::insert(kidot_loan::KD_PRICE_PAIR.to_vec(), price);
+				}
+			}
+
+			let whitelist: Vec<Vec<u8>> = vec![
+				// Block Number
+				hex_literal::hex!("26aa394eea5630e07c48ae0c9558cef702a5c1b19ab7a04f536c519aca4983ac").to_vec(),
+				// Total Issuance
+				hex_literal::hex!("c2261276cc9d1f8598ea4b6a74b15c2f57c875e4cff74148e4628f264b974c80").to_vec(),
+				// Execution Phase
+				hex_literal::hex!("26aa394eea5630e07c48ae0c9558cef7ff553b5a9862a516939d82b3d3d8661a").to_vec(),
+				// Event Count
+				hex_literal::hex!("26aa394eea5630e07c48ae0c9558cef70a98fdbe9ce6c55837576c60c7af3850").to_vec(),
+				// System Events
+				hex_literal::hex!("26aa394eea5630e07c48ae0c9558cef780d41e5e16056765bc8461851072c9d7").to_vec(),
+			];
+
+			let mut batches = Vec::<BenchmarkBatch>::new();
+			let params = (&pallet, &benchmark, &lowest_range_values, &highest_range_values, &steps, repeat, &whitelist);
+
+			add_benchmark!(params, batches, b"chainlink", ChainlinkBench::<Runtime>);
+			add_benchmark!(params, batches, b"pricefeed", Pricefeed);
+			add_benchmark!(params, batches, b"kidot-loan", KidotLoanBench::<Runtime>);
+
+			if batches.is_empty() { return Err("Benchmark not found for this pallet.".into()) }
+			Ok(batches)
+		}
+	}
 }